@@ -10,6 +10,7 @@ EXPECTED += [
     "Test write A OK!",
     "Test write B OK!",
     "Test write C OK!",
+    "Test efault OK!",
 ]
 
 EXPECTED += [
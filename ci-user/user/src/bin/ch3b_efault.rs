@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    syscall, TaskInfo, TimeVal, SYSCALL_GETTIMEOFDAY, SYSCALL_TASK_INFO, SYSCALL_WRITE,
+};
+
+/// 正确输出：（无报错信息）
+/// Test efault OK!
+
+const EFAULT: isize = -14;
+const STDOUT: usize = 1;
+
+// 内核的起始地址，不属于任何应用
+const KERNEL_ADDR: usize = 0x8020_0000;
+
+// 不可变的静态变量放在只读段里，内核不能往里写
+static READ_ONLY: [u64; 512] = [1; 512];
+
+#[no_mangle]
+fn main() -> i32 {
+    let read_only = READ_ONLY.as_ptr() as usize;
+    let mut buf = [0u64; 512];
+    // 栈上的缓冲区本身合法，加1之后只是没有对齐
+    let misaligned = buf.as_mut_ptr() as usize + 1;
+
+    // write只读用户缓冲区，只检查范围
+    assert_eq!(syscall(SYSCALL_WRITE, [STDOUT, 0, 16]), EFAULT);
+    assert_eq!(syscall(SYSCALL_WRITE, [STDOUT, KERNEL_ADDR, 16]), EFAULT);
+    assert_eq!(syscall(SYSCALL_WRITE, [STDOUT, usize::MAX - 7, 16]), EFAULT);
+
+    // get_time和task_info要往用户的结构体里写，还要检查对齐和可写
+    for ptr in [0, KERNEL_ADDR, misaligned, read_only] {
+        assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [ptr, 0, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_TASK_INFO, [ptr, 0, 0]), EFAULT);
+    }
+    assert!(core::mem::size_of::<TaskInfo>() <= core::mem::size_of_val(&READ_ONLY));
+    assert!(READ_ONLY.iter().all(|&x| x == 1));
+
+    // 出错之后内核照常工作
+    let mut time = TimeVal::new();
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [&mut time as *mut _ as usize, 0, 0]), 0);
+    assert!(time.sec > 0 || time.usec > 0);
+    println!("Test efault OK!");
+    0
+}
@@ -33,7 +33,7 @@ impl Write for ConsoleBuffer {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         for c in s.as_bytes().iter() {
             self.0.push_back(*c);
-            if (*c == b'\n' || self.0.len() == CONSOLE_BUFFER_SIZE) && -1 == self.flush() {
+            if (*c == b'\n' || self.0.len() == CONSOLE_BUFFER_SIZE) && self.flush() < 0 {
                 return Err(fmt::Error);
             }
         }
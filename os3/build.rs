@@ -1,17 +1,45 @@
+use std::env;
 use std::fs::{read_dir, File};
 use std::io::{Result, Write};
 
 fn main() {
     println!("cargo:rerun-if-changed=../user/src/");
     println!("cargo:rerun-if-changed={}", TARGET_PATH);
+    println!("cargo:rerun-if-env-changed=SCHED");
+    println!("cargo:rerun-if-env-changed=EXPECTED_FAULTS");
+    check_sched();
+    check_expected_faults();
     insert_app_data().unwrap();
 }
 
-static TARGET_PATH: &str = "../user/build/bin/";
+// 调度策略在编译时由SCHED选择，写错时直接让编译失败，而不是悄悄用默认策略
+static SCHEDULERS: [&str; 4] = ["RR", "FIFO", "PRIORITY", "STRIDE"];
+
+fn check_sched() {
+    if let Ok(sched) = env::var("SCHED") {
+        if !SCHEDULERS.contains(&sched.as_str()) {
+            panic!("Unknown SCHED={}, expected one of {:?}", sched, SCHEDULERS);
+        }
+    }
+}
+
+// 预期会触发异常的应用，格式为 应用名:scause,应用名:scause，写错时让编译失败
+fn check_expected_faults() {
+    if let Ok(faults) = env::var("EXPECTED_FAULTS") {
+        for item in faults.split(',').filter(|item| !item.is_empty()) {
+            match item.split_once(':') {
+                Some((app, scause)) if !app.is_empty() && scause.parse::<usize>().is_ok() => {}
+                _ => panic!("Bad EXPECTED_FAULTS entry {:?}, expected <app>:<scause>", item),
+            }
+        }
+    }
+}
+
+static TARGET_PATH: &str = "../user/build/elf/";
 
 fn insert_app_data() -> Result<()> {
     let mut f = File::create("src/link_app.S").unwrap();
-    let mut apps: Vec<_> = read_dir("../user/build/bin/")
+    let mut apps: Vec<_> = read_dir(TARGET_PATH)
         .unwrap()
         .into_iter()
         .map(|dir_entry| {
@@ -38,6 +66,16 @@ _num_app:
     }
     writeln!(f, r#"    .quad app_{}_end"#, apps.len() - 1)?;
 
+    writeln!(
+        f,
+        r#"
+    .global _app_names
+_app_names:"#
+    )?;
+    for app in apps.iter() {
+        writeln!(f, r#"    .string "{}""#, app)?;
+    }
+
     for (idx, app) in apps.iter().enumerate() {
         println!("app_{}: {}", idx, app);
         writeln!(
@@ -46,8 +84,9 @@ _num_app:
     .section .data
     .global app_{0}_start
     .global app_{0}_end
+    .align 3
 app_{0}_start:
-    .incbin "{2}{1}.bin"
+    .incbin "{2}{1}.elf"
 app_{0}_end:"#,
             idx, app, TARGET_PATH
         )?;
@@ -0,0 +1,75 @@
+# 链接之后从内核ELF中提取函数符号，写入预留的.ksym段，供panic时的栈回溯查找函数名
+# 格式见src/backtrace.rs，用法：python3 ksym.py target/riscv64gc-unknown-none-elf/release/os
+import os
+import re
+import struct
+import subprocess
+import sys
+
+magic = b"KSYMTAB\0"
+elf_path = sys.argv[1]
+nm = os.getenv("NM", "rust-nm")
+
+# 函数符号，去掉名字末尾的哈希
+symbols = []
+output = subprocess.run(
+    nm.split() + ["--defined-only", "--demangle", "-n", elf_path],
+    check=True,
+    capture_output=True,
+    text=True,
+).stdout
+for line in output.splitlines():
+    parts = line.split(" ", 2)
+    if len(parts) == 3 and parts[1] in "tT":
+        symbols.append((int(parts[0], 16), re.sub(r"::h[0-9a-f]{16}$", "", parts[2])))
+symbols.sort()
+
+# 泛型实例化后的名字可能很长，回溯时只看前面一段就够了
+MAX_NAME_LEN = 128
+
+
+def build(symbols):
+    names = b""
+    entries = b""
+    for addr, name in symbols:
+        entries += struct.pack("<QQ", addr, len(names))
+        names += name.encode()[:MAX_NAME_LEN] + b"\0"
+    return magic + struct.pack("<Q", len(symbols)) + entries + names
+
+
+table = build(symbols)
+
+# 在ELF64的节头表中找到.ksym段的文件偏移和大小
+with open(elf_path, "r+b") as f:
+    elf = f.read()
+    shoff, = struct.unpack_from("<Q", elf, 0x28)
+    shentsize, shnum, shstrndx = struct.unpack_from("<HHH", elf, 0x3A)
+
+    def section(i):
+        name, _, _, _, offset, size = struct.unpack_from("<IIQQQQ", elf, shoff + i * shentsize)
+        return name, offset, size
+
+    _, strtab, _ = section(shstrndx)
+    for i in range(shnum):
+        name, offset, size = section(i)
+        if elf[strtab + name : elf.index(b"\0", strtab + name)] == b".ksym":
+            break
+    else:
+        sys.exit("[ksym.py] no .ksym section in %s" % elf_path)
+    # 放不下时丢弃高地址的符号，只影响回溯时的函数名，不让构建失败
+    # 末尾补一个占位符号，避免被丢弃的函数被算到最后一个保留的符号名下
+    if len(table) > size:
+        needed = len(table)
+        kept = len(symbols)
+        while kept > 0 and len(table) > size:
+            kept = min(kept - 1, kept * size // len(table))
+            table = build(symbols[:kept] + [(symbols[kept][0], "?")])
+        print(
+            "[ksym.py] warning: symbol table needs %#x bytes but KSYM_SIZE is %#x, kept %d of %d symbols"
+            % (needed, size, kept, len(symbols)),
+            file=sys.stderr,
+        )
+        symbols = symbols[:kept]
+    f.seek(offset)
+    f.write(table.ljust(size, b"\0"))
+print("[ksym.py] %d symbols, %#x bytes" % (len(symbols), len(table)))
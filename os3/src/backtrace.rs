@@ -0,0 +1,108 @@
+// 基于帧指针的内核栈回溯，panic时打印调用链
+// 编译时打开了-Cforce-frame-pointers=yes，每个函数的栈帧里fp-8处存返回地址ra，fp-16处存调用者的fp
+// 符号表由ksym.py在链接之后从内核ELF中提取，写进预留的.ksym段，没写入时只打印地址
+
+use crate::config::KSYM_SIZE;
+use core::arch::asm;
+
+// 最多回溯的层数，防止栈被破坏时无限循环
+const MAX_DEPTH: usize = 32;
+
+// 符号表开头的魔数"KSYMTAB\0"，用来判断ksym.py是否已经写入
+const KSYM_MAGIC: u64 = u64::from_le_bytes(*b"KSYMTAB\0");
+
+// 为符号表预留的空间，格式为：
+// 魔数: u64，符号个数n: u64，n个(地址: u64, 名字在字符串区的偏移: u64)按地址升序排列，之后是以'\0'结尾的名字
+#[used]
+#[link_section = ".ksym"]
+static KSYM_SPACE: [u8; KSYM_SIZE] = [0; KSYM_SIZE];
+
+// 读取.ksym段中的第i个u64
+// 这里的内容是链接之后才写进去的，编译器看到的只是全零，所以必须通过链接脚本中的符号访问
+fn ksym_word(i: usize) -> u64 {
+    extern "C" {
+        fn sksym();
+    }
+    unsafe { (sksym as usize as *const u64).add(i).read_volatile() }
+}
+
+// 查找addr所在的函数，返回函数名和addr相对函数开头的偏移
+fn lookup(addr: usize) -> Option<(&'static str, usize)> {
+    extern "C" {
+        fn sksym();
+        fn eksym();
+    }
+    if ksym_word(0) != KSYM_MAGIC {
+        return None;
+    }
+    let num = ksym_word(1) as usize;
+    let entry = |i: usize| (ksym_word(2 + i * 2) as usize, ksym_word(3 + i * 2) as usize);
+    // 二分查找最后一个地址不大于addr的符号
+    let (mut lo, mut hi) = (0, num);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if entry(mid).0 <= addr {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    if lo == 0 {
+        return None;
+    }
+    let (sym_addr, name_off) = entry(lo - 1);
+    let names = sksym as usize + (2 + num * 2) * core::mem::size_of::<u64>();
+    let name = unsafe {
+        let start = (names + name_off) as *const u8;
+        let mut len = 0;
+        while start as usize + len < eksym as usize && start.add(len).read_volatile() != b'\0' {
+            len += 1;
+        }
+        core::str::from_utf8(core::slice::from_raw_parts(start, len)).ok()?
+    };
+    Some((name, addr - sym_addr))
+}
+
+// 帧指针是否指向内核映像之内，内核栈（启动栈和堆上的任务内核栈）都在这个范围里
+// 从trap_handler往上走会遇到用户态的s0，这里就会停下来
+fn is_kernel_fp(fp: usize) -> bool {
+    extern "C" {
+        fn skernel();
+        fn ekernel();
+    }
+    fp % core::mem::size_of::<usize>() == 0
+        && fp >= skernel as usize + 2 * core::mem::size_of::<usize>()
+        && fp <= ekernel as usize
+}
+
+// 沿着帧指针链打印当前的调用栈
+pub fn print_backtrace() {
+    let mut fp: usize;
+    unsafe {
+        asm!("mv {}, s0", out(reg) fp);
+    }
+    println!("[kernel] Backtrace:");
+    for depth in 0..MAX_DEPTH {
+        if !is_kernel_fp(fp) {
+            break;
+        }
+        let (ra, prev_fp) = unsafe { (*(fp as *const usize).sub(1), *(fp as *const usize).sub(2)) };
+        if ra == 0 {
+            break;
+        }
+        // ra指向call的下一条指令，减一再查，避免调用在函数末尾时查到下一个函数
+        match lookup(ra - 1) {
+            Some((name, offset)) => {
+                println!("  #{:<2} {:#x} <{}+{:#x}>", depth, ra, name, offset + 1);
+            }
+            None => {
+                println!("  #{:<2} {:#x}", depth, ra);
+            }
+        }
+        // 调用者的栈帧一定在更高的地址，否则栈已经被破坏
+        if prev_fp <= fp {
+            break;
+        }
+        fp = prev_fp;
+    }
+}
@@ -1,8 +1,19 @@
 pub const USER_STACK_SIZE: usize = 8192;
 pub const KERNEL_STACK_SIZE: usize = 8192 * 2;
-pub const KERNEL_HEAP_SIZE: usize = 0x20000;
-pub const MAX_APP_NUM: usize = 16;
-pub const APP_BASE_ADDRESS: usize = 0x80400000;
+// 每个任务的内核栈和用户栈共24KiB从堆上分配，任务表每项约2.3KiB，跟踪缓冲区32KiB
+// 24个应用时约用672KiB，剩下的留给应用镜像信息和伙伴分配器的碎片
+pub const KERNEL_HEAP_SIZE: usize = 0x100000;
+pub const MAX_APP_NUM: usize = 24; // 支持的最多应用数，同时运行的任务不会超过应用数
+pub const APP_BASE_ADDRESS: usize = 0x80400000; // 修改时同步 linker.ld 中对 ekernel 的检查
 pub const APP_SIZE_LIMIT: usize = 0x20000;
+pub const MEMORY_END: usize = 0x88000000;
+#[cfg(feature = "board_qemu")]
+pub const SIFIVE_TEST_BASE: usize = 0x100000;
 pub const CLOCK_FREQ: usize = 12500000;
 pub const MAX_SYSCALL_NUM: usize = 500;
+pub const BIG_STRIDE: usize = 0x100000;
+pub const DEFAULT_PRIORITY: usize = 16;
+pub const DEFAULT_TIME_SLICE_US: usize = 10_000;
+pub const KILL_ON_SYSCALL_ERROR: bool = false;
+pub const KSYM_SIZE: usize = 0x10000;
+pub const TRACE_CAPACITY: usize = 512;
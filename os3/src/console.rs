@@ -1,19 +1,172 @@
-use crate::sbi::console_putchar;
+#[cfg(not(feature = "uart"))]
+use crate::sbi::{console_getchar, console_write};
+use crate::sync::UPSafeCell;
+#[cfg(feature = "uart")]
+use crate::uart;
 use core::fmt::{self, Write};
+use lazy_static::*;
 
-struct Stdout;
+// 输出原始数据，不要求是合法的UTF-8
+#[cfg(not(feature = "uart"))]
+fn put_bytes(bytes: &[u8]) {
+    console_write(bytes);
+}
 
-impl Write for Stdout {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            console_putchar(c as usize);
+#[cfg(feature = "uart")]
+fn put_bytes(bytes: &[u8]) {
+    uart::write_bytes(bytes);
+}
+
+// 输出缓冲，一次print!或sys_write的内容先在栈上拼好，再一次性交给控制台，超长时分段输出
+const OUTPUT_BUFFER_SIZE: usize = 512;
+
+struct OutputBuffer {
+    data: [u8; OUTPUT_BUFFER_SIZE],
+    len: usize,
+    at_line_start: bool, // 已经写入的最后一个字节是不是换行
+}
+
+impl OutputBuffer {
+    fn new(at_line_start: bool) -> Self {
+        Self {
+            data: [0; OUTPUT_BUFFER_SIZE],
+            len: 0,
+            at_line_start,
+        }
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) {
+        if let Some(&last) = bytes.last() {
+            self.at_line_start = last == b'\n';
+        }
+        while !bytes.is_empty() {
+            if self.len == OUTPUT_BUFFER_SIZE {
+                self.flush();
+            }
+            let n = bytes.len().min(OUTPUT_BUFFER_SIZE - self.len);
+            self.data[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+            bytes = &bytes[n..];
         }
+    }
+
+    fn flush(&mut self) {
+        if self.len > 0 {
+            put_bytes(&self.data[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s.as_bytes());
         Ok(())
     }
 }
 
+// 编译时设置了CONSOLE_PREFIX时，应用输出的每一行前面加上任务ID，例如 CONSOLE_PREFIX=1 make run
+const CONSOLE_PREFIX: bool = option_env!("CONSOLE_PREFIX").is_some();
+
+// 控制台的行状态，只在加前缀时使用
+struct LineState {
+    writer: Option<usize>, // 最后一个输出的任务，None表示内核
+    at_line_start: bool,   // 下一个字节是不是行首
+}
+
+lazy_static! {
+    static ref LINE_STATE: UPSafeCell<LineState> = unsafe {
+        UPSafeCell::new(LineState {
+            writer: None,
+            at_line_start: true,
+        })
+    };
+}
+
+// 换成另一个输出者时，如果上一个输出者的行还没有结束，先换行，保证每行只属于一个输出者
+fn switch_writer(state: &mut LineState, out: &mut OutputBuffer, writer: Option<usize>) {
+    if state.writer != writer && !state.at_line_start {
+        out.push(b"\n");
+    }
+    state.writer = writer;
+}
+
+// 输出task_id对应任务通过sys_write写的一整块数据，加前缀时每行前面加上任务ID
+pub fn print_task_bytes(task_id: usize, bytes: &[u8]) {
+    if !CONSOLE_PREFIX {
+        put_bytes(bytes);
+        return;
+    }
+    let mut state = LINE_STATE.exclusive_access();
+    let mut out = OutputBuffer::new(state.at_line_start);
+    switch_writer(&mut state, &mut out, Some(task_id));
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        if out.at_line_start {
+            out.write_fmt(format_args!("[task {}] ", task_id)).unwrap();
+        }
+        out.push(line);
+    }
+    state.at_line_start = out.at_line_start;
+    out.flush();
+}
+
+// 读一个字符，没有输入时返回None
+// 不同的SBI实现在没有输入时返回0或-1，都当作没有输入
+#[cfg(not(feature = "uart"))]
+pub fn getchar() -> Option<u8> {
+    match console_getchar() {
+        0 | usize::MAX => None,
+        c => Some(c as u8),
+    }
+}
+
+#[cfg(feature = "uart")]
+pub fn getchar() -> Option<u8> {
+    uart::getchar()
+}
+
+// 把缓冲中的输出全部发送出去，SBI控制台没有缓冲
+#[cfg(not(feature = "uart"))]
+pub fn flush() {}
+
+#[cfg(feature = "uart")]
+pub fn flush() {
+    uart::flush();
+}
+
+// 初始化控制台，使用SBI时不需要
+#[cfg(not(feature = "uart"))]
+pub fn init() {}
+
+#[cfg(feature = "uart")]
+pub fn init() {
+    uart::init();
+}
+
+// 处理控制台的输入中断，使用SBI时没有中断
+#[cfg(not(feature = "uart"))]
+pub fn handle_interrupt() {}
+
+#[cfg(feature = "uart")]
+pub fn handle_interrupt() {
+    uart::handle_interrupt();
+}
+
+// 格式化输出，一次print!的所有片段拼好后一起输出
+// 加前缀时内核输出也参与行状态，打断任务还没结束的行时先换行
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    if !CONSOLE_PREFIX {
+        let mut out = OutputBuffer::new(true);
+        out.write_fmt(args).unwrap();
+        out.flush();
+        return;
+    }
+    let mut state = LINE_STATE.exclusive_access();
+    let mut out = OutputBuffer::new(state.at_line_start);
+    switch_writer(&mut state, &mut out, None);
+    out.write_fmt(args).unwrap();
+    state.at_line_start = out.at_line_start;
+    out.flush();
 }
 
 #[macro_export]
@@ -25,6 +178,9 @@ macro_rules! print {
 
 #[macro_export]
 macro_rules! println {
+    () => {
+        $crate::console::print(format_args!("\n"));
+    };
     ($fmt: literal $(, $($arg: tt)+)?) => {
         $crate::console::print(format_args!(concat!($fmt, "\n") $(, $($arg)+)?));
     }
@@ -1,4 +1,7 @@
+use crate::backtrace::print_backtrace;
+use crate::console::flush;
 use crate::sbi::shutdown;
+use crate::trace::dump_trace;
 use core::panic::PanicInfo;
 
 #[panic_handler]
@@ -13,5 +16,8 @@ fn panic(info: &PanicInfo) -> ! {
     } else {
         println!("[kernel] Panicked: {}", info.message().unwrap());
     }
-    shutdown()
+    print_backtrace();
+    dump_trace();
+    flush();
+    shutdown(true)
 }
\ No newline at end of file
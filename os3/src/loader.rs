@@ -1,27 +1,102 @@
 use crate::config::*;
+use crate::sync::UPSafeCell;
 use crate::trap::TrapContext;
+use alloc::alloc::{alloc_zeroed, Layout};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use lazy_static::*;
+use xmas_elf::{header, program, ElfFile};
 
 // 定义内核栈和用户栈结构
 #[repr(align(4096))]
-#[derive(Copy, Clone)]
 struct KernelStack {
     data: [u8; KERNEL_STACK_SIZE],
 }
 
 #[repr(align(4096))]
-#[derive(Copy, Clone)]
 struct UserStack {
     data: [u8; USER_STACK_SIZE],
 }
 
-// 实例化内核栈和用户栈，每个应用都有个独占的内核栈
-static KERNEL_STACK: [KernelStack; MAX_APP_NUM] = [KernelStack {
-    data: [0; KERNEL_STACK_SIZE],
-}; MAX_APP_NUM];
+lazy_static! {
+    // 每个任务独占的内核栈和用户栈，按任务ID索引，在堆上按需分配，任务结束后留给复用该位置的新任务
+    static ref KERNEL_STACK: UPSafeCell<Vec<Box<KernelStack>>> =
+        unsafe { UPSafeCell::new(Vec::new()) };
+    static ref USER_STACK: UPSafeCell<Vec<Box<UserStack>>> =
+        unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+// 栈底的保护字，栈向下增长，溢出时最先被改写的就是它们
+const STACK_CANARY: usize = 0x57ac_c0de_57ac_c0de;
+const CANARY_WORDS: usize = 4;
+// 栈中未使用的部分填充的字节，任务结束时从栈底往上找第一个被改写的字节，得到栈的最大使用量
+const STACK_PAINT: u8 = 0xa5;
+
+// 溢出的是哪一个栈
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StackKind {
+    Kernel,
+    User,
+}
+
+// 给栈填充标记字节，并在栈底写入保护字
+fn paint_stack(data: &mut [u8]) {
+    data.fill(STACK_PAINT);
+    let canary = STACK_CANARY.to_ne_bytes();
+    data[..canary.len() * CANARY_WORDS]
+        .chunks_exact_mut(canary.len())
+        .for_each(|word| word.copy_from_slice(&canary));
+}
+
+// 栈底的保护字是否完好
+fn canary_intact(data: &[u8]) -> bool {
+    let canary = STACK_CANARY.to_ne_bytes();
+    data[..canary.len() * CANARY_WORDS]
+        .chunks_exact(canary.len())
+        .all(|word| word == canary)
+}
 
-static USER_STACK: [UserStack; MAX_APP_NUM] = [UserStack {
-    data: [0; USER_STACK_SIZE],
-}; MAX_APP_NUM];
+// 栈曾经使用过的最大字节数
+fn stack_used(data: &[u8]) -> usize {
+    let guard = core::mem::size_of::<usize>() * CANARY_WORDS;
+    let untouched = data[guard..]
+        .iter()
+        .take_while(|&&byte| byte == STACK_PAINT)
+        .count();
+    data.len() - guard - untouched
+}
+
+// 在堆上分配一块清零的内存放栈，不经过内核栈中转，堆空间不足时返回None而不是直接panic
+fn alloc_stack<T>() -> Option<Box<T>> {
+    let ptr = unsafe { alloc_zeroed(Layout::new::<T>()) } as *mut T;
+    if ptr.is_null() {
+        None
+    } else {
+        // 栈里只有字节数组，全零就是合法的值
+        Some(unsafe { Box::from_raw(ptr) })
+    }
+}
+
+// 确保task_id对应的任务有内核栈和用户栈，任务ID是连续分配的，所以最多只需要新增一组
+fn ensure_stacks(task_id: usize) -> bool {
+    let mut kernel_stacks = KERNEL_STACK.exclusive_access();
+    let mut user_stacks = USER_STACK.exclusive_access();
+    if task_id < kernel_stacks.len() {
+        return true;
+    }
+    // 先为栈表预留位置，push 时扩容失败会直接中止内核
+    if kernel_stacks.try_reserve(1).is_err() || user_stacks.try_reserve(1).is_err() {
+        return false;
+    }
+    match (alloc_stack::<KernelStack>(), alloc_stack::<UserStack>()) {
+        (Some(kernel_stack), Some(user_stack)) => {
+            kernel_stacks.push(kernel_stack);
+            user_stacks.push(user_stack);
+            true
+        }
+        _ => false,
+    }
+}
 
 impl KernelStack {
 
@@ -49,22 +124,61 @@ impl UserStack {
     }
 }
 
-// 计算各个应用的加载基址
-fn get_base_i(app_id: usize) -> usize {
-    // 每个应用的加载基址为总基址 + 应用的顺位id * 每个应用固定分配的大小区域
-    APP_BASE_ADDRESS + app_id * APP_SIZE_LIMIT
+// 检查task_id对应任务的两个栈底的保护字，返回溢出了的栈
+pub fn find_stack_overflow(task_id: usize) -> Option<StackKind> {
+    if !canary_intact(&KERNEL_STACK.exclusive_access()[task_id].data) {
+        Some(StackKind::Kernel)
+    } else if !canary_intact(&USER_STACK.exclusive_access()[task_id].data) {
+        Some(StackKind::User)
+    } else {
+        None
+    }
 }
 
-// 获取个数数值
-pub fn get_num_app() -> usize {
-    extern "C" {
-        fn _num_app();
+// task_id对应任务的内核栈和用户栈曾经使用过的最大字节数，用来确定栈开多大合适
+pub fn stack_high_water(task_id: usize) -> (usize, usize) {
+    (
+        stack_used(&KERNEL_STACK.exclusive_access()[task_id].data),
+        stack_used(&USER_STACK.exclusive_access()[task_id].data),
+    )
+}
+
+// 应用映像在内存中占据的区域[start, end)，由ELF程序头中各个LOAD段的虚拟地址决定
+// 没有开启分页，虚拟地址就是物理地址，应用链接在哪里就放在哪里，与应用在link_app.S中的顺序无关
+#[derive(Copy, Clone)]
+pub struct AppRegion {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl AppRegion {
+    fn contains(&self, start: usize, end: usize) -> bool {
+        start >= self.start && end <= self.end
+    }
+
+    fn overlaps(&self, other: &AppRegion) -> bool {
+        self.start < other.end && other.start < self.end
     }
-    unsafe { (_num_app as usize as *const usize).read_volatile() }
 }
 
-// 加载应用
-pub fn load_apps() {
+// ELF中的一个LOAD段，[start, start + file_size)从文件中复制，剩下的部分直到end是BSS，要清零
+struct AppSegment {
+    region: AppRegion,
+    offset: usize,
+    file_size: usize,
+    writable: bool,
+    executable: bool,
+}
+
+// 解析好的应用映像
+struct AppImage {
+    region: AppRegion,         // 所有段合起来占据的区域
+    entry: usize,              // 入口地址，来自e_entry
+    segments: Vec<AppSegment>, // 各个LOAD段
+}
+
+// 获取ID对应应用的ELF数据
+fn get_app_data(app_id: usize) -> &'static [u8] {
     // 引入符号，这个符号来自link_app.S，是一个存着应用总个数的地址
     extern "C" {
         fn _num_app();
@@ -73,39 +187,247 @@ pub fn load_apps() {
     let num_app_ptr = _num_app as usize as *const usize;
     // 获取应用数数值
     let num_app = get_num_app();
-
     // 用刚才得到的裸指针和应用数数值生成切片，里面有所有的应用的符号（编译时转换为各个应用的地址，见link_app.S）
     let app_start = unsafe { core::slice::from_raw_parts(num_app_ptr.add(1), num_app + 1) };
-
-    // 清理i-cache，直接用内联汇编指令
     unsafe {
-        core::arch::asm!("fence.i");
+        core::slice::from_raw_parts(
+            app_start[app_id] as *const u8,
+            app_start[app_id + 1] - app_start[app_id],
+        )
     }
+}
 
+// 解析应用的ELF，得到各个段和入口，不能加载时返回原因
+fn parse_app_image(app_id: usize) -> Result<AppImage, &'static str> {
+    let data = get_app_data(app_id);
+    let elf = ElfFile::new(data)?;
+    // 没有分页也就没法重定位，只支持按固定地址链接的可执行文件
+    if elf.header.pt2.type_().as_type() != header::Type::Executable {
+        return Err("not a statically linked executable");
+    }
+    let mut region = AppRegion {
+        start: usize::MAX,
+        end: 0,
+    };
+    let mut segments = Vec::new();
+    for ph in elf.program_iter() {
+        if ph.get_type()? != program::Type::Load {
+            continue;
+        }
+        let (offset, file_size) = (ph.offset() as usize, ph.file_size() as usize);
+        if ph.file_size() > ph.mem_size() || offset + file_size > data.len() {
+            return Err("malformed LOAD segment");
+        }
+        let seg = AppRegion {
+            start: ph.virtual_addr() as usize,
+            end: (ph.virtual_addr() + ph.mem_size()) as usize,
+        };
+        region.start = region.start.min(seg.start);
+        region.end = region.end.max(seg.end);
+        segments.push(AppSegment {
+            region: seg,
+            offset,
+            file_size,
+            writable: ph.flags().is_write(),
+            executable: ph.flags().is_execute(),
+        });
+    }
+    let entry = elf.header.pt2.entry_point() as usize;
+    if region.start >= region.end {
+        Err("no loadable segment")
+    } else if region.start < APP_BASE_ADDRESS || region.end > MEMORY_END {
+        Err("linked outside of user memory")
+    } else if region.end - region.start > APP_SIZE_LIMIT {
+        Err("larger than APP_SIZE_LIMIT")
+    } else if !segments
+        .iter()
+        .any(|seg| seg.executable && seg.region.contains(entry, entry + 1))
+    {
+        Err("entry point not in an executable segment")
+    } else {
+        Ok(AppImage {
+            region,
+            entry,
+            segments,
+        })
+    }
+}
+
+lazy_static! {
+    // 各个应用解析好的映像，超出范围或与其他应用重叠的应用为None，不会被启动
+    static ref APP_IMAGES: Vec<Option<AppImage>> = {
+        let mut images: Vec<Option<AppImage>> = Vec::new();
+        for i in 0..get_num_app() {
+            let image = match parse_app_image(i) {
+                Ok(image)
+                    if images
+                        .iter()
+                        .flatten()
+                        .any(|other| other.region.overlaps(&image.region)) =>
+                {
+                    Err("overlaps with another app")
+                }
+                other => other,
+            };
+            images.push(match image {
+                Ok(image) => Some(image),
+                Err(reason) => {
+                    println!("[kernel] Rejected app {}: {}", get_app_name(i), reason);
+                    None
+                }
+            });
+        }
+        images
+    };
+}
+
+// 获取ID对应应用的加载区域，不能加载的应用返回None
+pub fn get_app_region(app_id: usize) -> Option<AppRegion> {
+    APP_IMAGES[app_id].as_ref().map(|image| image.region)
+}
+
+// 检查[start, start + len)是否完全位于app_id对应应用的某个段或task_id对应任务的用户栈之内
+// 内核要往里写（writable为true）时，所在的段必须是可写的，不能借系统调用改写应用的代码和只读数据
+// 系统调用在访问用户传进来的指针前都要先检查，防止坏指针破坏内核
+pub fn check_user_range(
+    app_id: usize,
+    task_id: usize,
+    start: usize,
+    len: usize,
+    writable: bool,
+) -> bool {
+    let end = match start.checked_add(len) {
+        Some(end) => end,
+        None => return false,
+    };
+    let in_app = APP_IMAGES[app_id].as_ref().map_or(false, |image| {
+        image
+            .segments
+            .iter()
+            .any(|seg| (seg.writable || !writable) && seg.region.contains(start, end))
+    });
+    let stack_bottom = USER_STACK.exclusive_access()[task_id].data.as_ptr() as usize;
+    in_app || (start >= stack_bottom && end <= stack_bottom + USER_STACK_SIZE)
+}
+
+// 检查用户传进来接收结果的结构体指针，除了范围和可写还要检查对齐
+pub fn check_user_ptr<T>(app_id: usize, task_id: usize, ptr: *const T) -> bool {
+    ptr as usize % core::mem::align_of::<T>() == 0
+        && check_user_range(app_id, task_id, ptr as usize, core::mem::size_of::<T>(), true)
+}
+
+// 读取用户传进来的以'\0'结尾的字符串，越界或不是合法的UTF-8时返回None
+pub fn read_user_str(app_id: usize, task_id: usize, ptr: *const u8) -> Option<&'static str> {
+    let mut len = 0;
+    loop {
+        if !check_user_range(app_id, task_id, ptr as usize + len, 1, false) {
+            return None;
+        }
+        if unsafe { ptr.add(len).read_volatile() } == b'\0' {
+            break;
+        }
+        len += 1;
+    }
+    core::str::from_utf8(unsafe { core::slice::from_raw_parts(ptr, len) }).ok()
+}
+
+// 获取个数数值
+pub fn get_num_app() -> usize {
+    extern "C" {
+        fn _num_app();
+    }
+    unsafe { (_num_app as usize as *const usize).read_volatile() }
+}
+
+lazy_static! {
+    // 各个应用的名字，来自link_app.S中的_app_names，是一串以'\0'结尾的字符串
+    static ref APP_NAMES: Vec<&'static str> = {
+        let num_app = get_num_app();
+        extern "C" {
+            fn _app_names();
+        }
+        let mut start = _app_names as usize as *const u8;
+        let mut v = Vec::new();
+        unsafe {
+            for _ in 0..num_app {
+                let mut end = start;
+                while end.read_volatile() != b'\0' {
+                    end = end.add(1);
+                }
+                let slice = core::slice::from_raw_parts(start, end as usize - start as usize);
+                let str = core::str::from_utf8(slice).unwrap();
+                v.push(str);
+                start = end.add(1);
+            }
+        }
+        v
+    };
+}
+
+// 获取ID对应应用的名字
+pub fn get_app_name(app_id: usize) -> &'static str {
+    APP_NAMES[app_id]
+}
+
+// 根据名字查找应用的ID
+pub fn get_app_id_by_name(name: &str) -> Option<usize> {
+    APP_NAMES.iter().position(|&app| app == name)
+}
+
+// 加载应用，不能加载的应用跳过
+pub fn load_apps() {
     // 用迭代器加载应用
-    for i in 0..num_app {
-        // 获取各个应用被加载到的基址
-        let base_i = get_base_i(i);
-        // 先清零区域，从应用基址清理应用固定的大小
-        (base_i..base_i + APP_SIZE_LIMIT)
-            .for_each(|addr| unsafe { (addr as *mut u8).write_volatile(0) });
-        // 应用源地址，相当于外存
-        let src = unsafe {
-            core::slice::from_raw_parts(app_start[i] as *const u8, app_start[i + 1] - app_start[i])
+    for i in 0..get_num_app() {
+        if APP_IMAGES[i].is_some() {
+            load_app(i);
+        }
+    }
+}
+
+// 把ID对应的应用的各个段加载到它们链接时的地址，重新运行一个已经结束的应用前也要调用
+pub fn load_app(app_id: usize) {
+    let image = APP_IMAGES[app_id]
+        .as_ref()
+        .expect("load_app on a rejected app");
+    // 应用源地址，相当于外存
+    let data = get_app_data(app_id);
+    for seg in image.segments.iter() {
+        let src = &data[seg.offset..seg.offset + seg.file_size];
+        // 应用目标地址，相当于内存，p_memsz比p_filesz多出来的部分是BSS
+        let dst = unsafe {
+            core::slice::from_raw_parts_mut(
+                seg.region.start as *mut u8,
+                seg.region.end - seg.region.start,
+            )
         };
-        // 应用目标地址，相当于内存
-        let dst = unsafe { core::slice::from_raw_parts_mut(base_i as *mut u8, src.len()) };
-        // 装载
-        dst.copy_from_slice(src);
+        // 装载，再把BSS清零，上一次运行留下的数据不能带到这一次
+        let (file, bss) = dst.split_at_mut(seg.file_size);
+        file.copy_from_slice(src);
+        bss.iter_mut()
+            .for_each(|byte| unsafe { (byte as *mut u8).write_volatile(0) });
+    }
+
+    // 改写了指令所在的内存，清理i-cache，直接用内联汇编指令
+    unsafe {
+        core::arch::asm!("fence.i");
     }
 }
 
 
-// 在ID对应的应用的内核栈中压入该应用的Trap上下文
-pub fn init_app_cx(app_id: usize) -> usize {
-    // 调用ID对应的应用的内核栈的压栈方法，把下面构造的Trap上下文压进去
-    KERNEL_STACK[app_id].push_context(TrapContext::app_init_context( // 构造Trap上下文
-        get_base_i(app_id), // 对应ID应用的加载位置
-        USER_STACK[app_id].get_sp(), // 对应ID应用的用户栈初始栈顶（也就是栈最底部）
-    )) // 返回新栈顶
+// 在task_id对应任务的内核栈中压入运行app_id对应应用的Trap上下文，返回新栈顶
+// 应用不能加载或者堆空间不足、分配不出栈时返回None
+pub fn init_app_cx(app_id: usize, task_id: usize) -> Option<usize> {
+    let entry = APP_IMAGES[app_id].as_ref()?.entry;
+    if !ensure_stacks(task_id) {
+        return None;
+    }
+    // 重新填充两个栈，task_id对应的旧任务已经结束，栈里的内容都不需要了
+    paint_stack(&mut KERNEL_STACK.exclusive_access()[task_id].data);
+    paint_stack(&mut USER_STACK.exclusive_access()[task_id].data);
+    let user_sp = USER_STACK.exclusive_access()[task_id].get_sp();
+    // 调用ID对应的任务的内核栈的压栈方法，把下面构造的Trap上下文压进去
+    Some(KERNEL_STACK.exclusive_access()[task_id].push_context(TrapContext::app_init_context( // 构造Trap上下文
+        entry, // 对应ID应用的入口地址
+        user_sp, // 对应ID任务的用户栈初始栈顶（也就是栈最底部）
+    ))) // 返回新栈顶
 }
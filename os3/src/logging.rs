@@ -1,15 +1,34 @@
 // 使用了外部库https://docs.rs/log/latest/log/index.html
+use crate::timer::get_time_us;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use log::{self, Level, LevelFilter, Log, Metadata, Record};
 
+// 编译时的环境变量LOG，由逗号分隔的若干条规则组成，例如 LOG=task=trace,trap=warn,info
+// 只写级别的规则是默认级别，"模块=级别"只作用于该模块及其子模块，模块名不带crate名，匹配最长的那条
+static LOG_SPEC: Option<&str> = option_env!("LOG");
+
+// 编译时设置了LOG_PREFIX时，在每条日志前加上时间戳和当前任务
+static LOG_PREFIX: bool = option_env!("LOG_PREFIX").is_some();
+
+// 表示"没有"的值，用于下面两个原子变量
+const NONE: usize = usize::MAX;
+
+// 运行时通过sys_set_log_level设置的级别，设置后覆盖LOG中的所有规则
+// 日志可能在任何地方打印，包括已经借用了任务表的时候，所以这里用原子变量而不是UPSafeCell
+static LEVEL_OVERRIDE: AtomicUsize = AtomicUsize::new(NONE);
+
+// 当前运行的任务，由任务切换时更新，原因同上
+static CURRENT_TASK: AtomicUsize = AtomicUsize::new(NONE);
+
 // 自定义一个精简的Logger
 struct SimpleLogger;
 
 // 为其实现Log特征
 impl Log for SimpleLogger {
     // 确定哪些需要被记录
-    fn enabled(&self, _metadata: &Metadata) -> bool {
-        // 这里设置的是完全记录
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= level_for(metadata.target())
     }
     // 如何输出或存储记录，这里使用了println!
     fn log(&self, record: &Record) {
@@ -26,28 +45,132 @@ impl Log for SimpleLogger {
             Level::Trace => 90, // BrightBlack
         };
         // 使用println!打印出来
-        println!(
-            "\u{1B}[{}m[{:>5}] {}\u{1B}[0m",
-            color,
-            record.level(),
-            record.args(),
-        );
+        if LOG_PREFIX {
+            let us = get_time_us();
+            println!(
+                "\u{1B}[{}m[{:>5}][{:>4}.{:06}][{}] {}\u{1B}[0m",
+                color,
+                record.level(),
+                us / 1_000_000,
+                us % 1_000_000,
+                TaskTag(CURRENT_TASK.load(Ordering::Relaxed)),
+                record.args(),
+            );
+        } else {
+            println!(
+                "\u{1B}[{}m[{:>5}] {}\u{1B}[0m",
+                color,
+                record.level(),
+                record.args(),
+            );
+        }
     }
     // 刷新缓冲
     fn flush(&self) {}
 }
 
+// 日志前缀中的任务编号，还没有任务运行时显示"-"
+struct TaskTag(usize);
+
+impl fmt::Display for TaskTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            NONE => write!(f, "{:>3}", "-"),
+            task => write!(f, "{:>3}", task),
+        }
+    }
+}
+
+// 解析级别，大小写都可以，兼容原来的 LOG=INFO 写法
+fn parse_level(s: &str) -> Option<LevelFilter> {
+    s.trim().parse().ok()
+}
+
+// module是否是target本身或者它的上级模块，target形如"os::task::scheduler"
+fn module_matches(target: &str, module: &str) -> bool {
+    let path = target.split_once("::").map_or("", |(_, path)| path);
+    let module = module.trim();
+    path == module
+        || (path.starts_with(module) && path[module.len()..].starts_with("::"))
+}
+
+// 按LOG中的规则算出target可以打印的最大级别
+fn spec_level(target: &str) -> LevelFilter {
+    let mut default = LevelFilter::Off;
+    let mut best: Option<(usize, LevelFilter)> = None;
+    for directive in LOG_SPEC.unwrap_or("").split(',') {
+        match directive.split_once('=') {
+            Some((module, level)) => {
+                if let Some(level) = parse_level(level) {
+                    let len = module.trim().len();
+                    if module_matches(target, module) && best.map_or(true, |(l, _)| len >= l) {
+                        best = Some((len, level));
+                    }
+                }
+            }
+            None => {
+                if let Some(level) = parse_level(directive) {
+                    default = level;
+                }
+            }
+        }
+    }
+    best.map_or(default, |(_, level)| level)
+}
+
+// target当前可以打印的最大级别
+fn level_for(target: &str) -> LevelFilter {
+    match LEVEL_OVERRIDE.load(Ordering::Relaxed) {
+        NONE => spec_level(target),
+        level => level_filter(level),
+    }
+}
+
+// LevelFilter按Off=0到Trace=5编号
+pub fn level_filter(level: usize) -> LevelFilter {
+    match level {
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        _ => LevelFilter::Off,
+    }
+}
+
+// LOG中所有规则里最宽松的级别，log库先用它过滤一遍
+fn spec_max_level() -> LevelFilter {
+    LOG_SPEC
+        .unwrap_or("")
+        .split(',')
+        .filter_map(|directive| parse_level(directive.rsplit('=').next().unwrap()))
+        .max()
+        .unwrap_or(LevelFilter::Off)
+}
+
+// 运行时设置所有模块的级别，level为None时恢复LOG中的规则
+pub fn set_level_override(level: Option<LevelFilter>) {
+    match level {
+        Some(level) => {
+            LEVEL_OVERRIDE.store(level as usize, Ordering::Relaxed);
+            log::set_max_level(level);
+        }
+        None => {
+            LEVEL_OVERRIDE.store(NONE, Ordering::Relaxed);
+            log::set_max_level(spec_max_level());
+        }
+    }
+}
+
+// 任务切换时告诉logger当前运行的任务
+pub fn set_current_task(task_id: usize) {
+    CURRENT_TASK.store(task_id, Ordering::Relaxed);
+}
+
 // 初始化logger
 pub fn init() {
     static LOGGER: SimpleLogger = SimpleLogger;
     log::set_logger(&LOGGER).unwrap();
-    // 根据运行程序时设置的环境变量来选择打印最大级别
-    log::set_max_level(match option_env!("LOG") {
-        Some("ERROR") => LevelFilter::Error,
-        Some("WARN") => LevelFilter::Warn,
-        Some("INFO") => LevelFilter::Info,
-        Some("DEBUG") => LevelFilter::Debug,
-        Some("TRACE") => LevelFilter::Trace,
-        _ => LevelFilter::Off,
-    });
+    // 根据编译时设置的环境变量LOG选择打印最大级别
+    log::set_max_level(spec_max_level());
 }
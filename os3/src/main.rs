@@ -17,6 +17,7 @@ extern crate alloc;
 
 #[macro_use]
 mod console;
+mod backtrace;
 mod config;
 mod heap_alloc;
 mod lang_items;
@@ -27,7 +28,10 @@ mod sync;
 mod syscall;
 mod task;
 mod timer;
+mod trace;
 mod trap;
+#[cfg(feature = "uart")]
+mod uart;
 
 // 内联入口点汇编
 core::arch::global_asm!(include_str!("entry.asm"));
@@ -54,6 +58,7 @@ pub fn rust_main() -> ! {
     println!("[kernel] Hello, world!");
     heap_alloc::init_heap(); // 初始化堆？？？为什么现在就有堆了
     trap::init(); // 初始化trap，处理所有的U陷入S
+    console::init(); // 初始化控制台，使用串口时打开它的输入中断
     loader::load_apps(); // 加载应用
     trap::enable_timer_interrupt(); // 启用时间中断，使得 S 特权级时钟中断不会被屏蔽
     timer::set_next_trigger(); //设置第一次中断
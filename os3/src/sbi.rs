@@ -1,10 +1,54 @@
 #![allow(unused)]
 
+#[cfg(feature = "board_qemu")]
+use crate::config::SIFIVE_TEST_BASE;
+use lazy_static::*;
+
+// SBI v0.1的传统调用，a7中放调用号，a0返回结果
 const SBI_SET_TIMER: usize = 0;
 const SBI_CONSOLE_PUTCHAR: usize = 1;
 const SBI_CONSOLE_GETCHAR: usize = 2;
 const SBI_SHUTDOWN: usize = 8;
 
+// SBI v0.2起按扩展组织调用，a7中放扩展号EID，a6中放功能号FID，a0返回错误码，a1返回值
+const EID_BASE: usize = 0x10;
+const EID_TIME: usize = 0x5449_4D45; // "TIME"
+const EID_SRST: usize = 0x5352_5354; // "SRST"
+const EID_DBCN: usize = 0x4442_434E; // "DBCN"
+
+// Base扩展的功能号
+const BASE_GET_SPEC_VERSION: usize = 0;
+const BASE_PROBE_EXTENSION: usize = 3;
+
+// TIME扩展的功能号
+const TIME_SET_TIMER: usize = 0;
+
+// SRST扩展的功能号和参数
+const SRST_SYSTEM_RESET: usize = 0;
+const SRST_TYPE_SHUTDOWN: usize = 0;
+const SRST_REASON_NONE: usize = 0;
+const SRST_REASON_FAILURE: usize = 1;
+
+// DBCN扩展的功能号
+const DBCN_CONSOLE_WRITE: usize = 0;
+const DBCN_CONSOLE_READ: usize = 1;
+const DBCN_CONSOLE_WRITE_BYTE: usize = 2;
+
+// QEMU virt板上sifive_test设备的命令，写入后QEMU立即退出，失败时高16位是退出码
+#[cfg(feature = "board_qemu")]
+const SIFIVE_TEST_PASS: u32 = 0x5555;
+#[cfg(feature = "board_qemu")]
+const SIFIVE_TEST_FAIL: u32 = 0x3333;
+
+// 错误码
+const SBI_SUCCESS: isize = 0;
+
+// v0.2调用的返回值
+struct SbiRet {
+    error: isize,
+    value: usize,
+}
+
 #[inline(always)]
 fn sbi_call(which: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
     let mut ret;
@@ -20,19 +64,129 @@ fn sbi_call(which: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
     ret
 }
 
+#[inline(always)]
+fn sbi_call_ext(eid: usize, fid: usize, arg0: usize, arg1: usize, arg2: usize) -> SbiRet {
+    let (error, value);
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") arg0 => error,
+            inlateout("x11") arg1 => value,
+            in("x12") arg2,
+            in("x16") fid,
+            in("x17") eid,
+        );
+    }
+    SbiRet { error, value }
+}
+
+// SBI实现支持的扩展，第一次使用时探测
+struct Extensions {
+    time: bool,
+    srst: bool,
+    dbcn: bool,
+}
+
+// 只支持v0.1的实现没有Base扩展，对不认识的调用返回错误，此时全部使用传统调用
+fn probe_extensions() -> Extensions {
+    let version = sbi_call_ext(EID_BASE, BASE_GET_SPEC_VERSION, 0, 0, 0);
+    let probe = |eid| {
+        version.error == SBI_SUCCESS
+            && sbi_call_ext(EID_BASE, BASE_PROBE_EXTENSION, eid, 0, 0).value != 0
+    };
+    Extensions {
+        time: probe(EID_TIME),
+        srst: probe(EID_SRST),
+        dbcn: probe(EID_DBCN),
+    }
+}
+
+lazy_static! {
+    static ref EXTENSIONS: Extensions = probe_extensions();
+}
+
 pub fn set_timer(timer: usize) {
-    sbi_call(SBI_SET_TIMER, timer, 0, 0);
+    if EXTENSIONS.time {
+        sbi_call_ext(EID_TIME, TIME_SET_TIMER, timer, 0, 0);
+    } else {
+        sbi_call(SBI_SET_TIMER, timer, 0, 0);
+    }
 }
 
 pub fn console_putchar(c: usize) {
-    sbi_call(SBI_CONSOLE_PUTCHAR, c, 0, 0);
+    if EXTENSIONS.dbcn {
+        sbi_call_ext(EID_DBCN, DBCN_CONSOLE_WRITE_BYTE, c, 0, 0);
+    } else {
+        sbi_call(SBI_CONSOLE_PUTCHAR, c, 0, 0);
+    }
 }
 
+// 输出一段字节，DBCN可以一次输出整个缓冲区，没有分页，缓冲区的地址就是物理地址
+pub fn console_write(bytes: &[u8]) {
+    if !EXTENSIONS.dbcn {
+        bytes.iter().for_each(|&b| console_putchar(b as usize));
+        return;
+    }
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let ret = sbi_call_ext(
+            EID_DBCN,
+            DBCN_CONSOLE_WRITE,
+            rest.len(),
+            rest.as_ptr() as usize,
+            0,
+        );
+        if ret.error != SBI_SUCCESS {
+            break;
+        }
+        rest = &rest[ret.value.min(rest.len())..];
+    }
+}
+
+// 读一个字符，没有输入时返回usize::MAX，与传统调用一致
 pub fn console_getchar() -> usize {
-    sbi_call(SBI_CONSOLE_GETCHAR, 0, 0, 0)
+    if EXTENSIONS.dbcn {
+        let mut c = 0u8;
+        let ret = sbi_call_ext(
+            EID_DBCN,
+            DBCN_CONSOLE_READ,
+            1,
+            &mut c as *mut u8 as usize,
+            0,
+        );
+        if ret.error == SBI_SUCCESS && ret.value == 1 {
+            c as usize
+        } else {
+            usize::MAX
+        }
+    } else {
+        sbi_call(SBI_CONSOLE_GETCHAR, 0, 0, 0)
+    }
 }
 
-pub fn shutdown() -> ! {
+// 关机，failure为true时告诉SBI是因为出错而关机，QEMU据此返回非零的退出码
+// SBI不支持SRST时直接写sifive_test设备，传统的关机调用无法区分成功和失败，只作为最后的手段
+pub fn shutdown(failure: bool) -> ! {
+    if EXTENSIONS.srst {
+        let reason = if failure {
+            SRST_REASON_FAILURE
+        } else {
+            SRST_REASON_NONE
+        };
+        sbi_call_ext(EID_SRST, SRST_SYSTEM_RESET, SRST_TYPE_SHUTDOWN, reason, 0);
+    }
+    // 只有QEMU virt板子上才有sifive_test设备，k210上这个地址不能乱写
+    #[cfg(feature = "board_qemu")]
+    {
+        let command = if failure {
+            (1 << 16) | SIFIVE_TEST_FAIL
+        } else {
+            SIFIVE_TEST_PASS
+        };
+        unsafe {
+            (SIFIVE_TEST_BASE as *mut u32).write_volatile(command);
+        }
+    }
     sbi_call(SBI_SHUTDOWN, 0, 0, 0);
     panic!("It should shutdown!");
 }
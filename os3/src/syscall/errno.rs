@@ -0,0 +1,12 @@
+#![allow(unused)]
+
+// 与Linux一致的错误码，系统调用出错时返回它们的相反数
+
+pub const ENOENT: isize = 2; // 没有这个应用
+pub const ESRCH: isize = 3; // 没有这个任务
+pub const EBADF: isize = 9; // 无效的文件描述符
+pub const ENOMEM: isize = 12; // 内核堆空间不足
+pub const EFAULT: isize = 14; // 用户指针不在应用的地址范围内
+pub const EBUSY: isize = 16; // 资源正在使用
+pub const EINVAL: isize = 22; // 参数不合法
+pub const ENOSYS: isize = 38; // 不支持的系统调用
@@ -1,5 +1,12 @@
 // 字符读写相关的系统调用
 
+use super::errno::{EBADF, EFAULT};
+use crate::console::{getchar, print_task_bytes};
+use crate::loader::check_user_range;
+use crate::task::{current_app_id, current_task_id, suspend_current_and_run_next};
+
+// 从键盘读入
+const FD_STDIN: usize = 0;
 // 打印至缓冲区
 const FD_STDOUT: usize = 1;
 
@@ -7,15 +14,51 @@ const FD_STDOUT: usize = 1;
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     match fd {
         FD_STDOUT => {
-            // 从指针和长度转化为str
+            // 缓冲区不在当前应用的地址范围内，返回EFAULT
+            if !check_user_range(current_app_id(), current_task_id(), buf as usize, len, false) {
+                return -EFAULT;
+            }
+            // 从指针和长度转化为切片，按原始字节整块输出，不要求是合法的UTF-8
             let slice = unsafe { core::slice::from_raw_parts(buf, len) };
-            let str = core::str::from_utf8(slice).unwrap();
-            // 使用print!打印
-            print!("{}", str);
+            print_task_bytes(current_task_id(), slice);
             len as isize
         }
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+        _ => -EBADF,
+    }
+}
+
+// 从标准输入读取最多len个字节，至少读到一个字节才返回，返回实际读到的字节数
+pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> isize {
+    match fd {
+        FD_STDIN => {
+            if !check_user_range(current_app_id(), current_task_id(), buf as usize, len, true) {
+                return -EFAULT;
+            }
+            if len == 0 {
+                return 0;
+            }
+            // 还没有输入时让出CPU，下次被调度时再看
+            let first = loop {
+                match getchar() {
+                    Some(c) => break c,
+                    None => suspend_current_and_run_next(),
+                }
+            };
+            let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
+            slice[0] = first;
+            // 已经到达的输入一并读走，但不再等待
+            let mut count = 1;
+            while count < len {
+                match getchar() {
+                    Some(c) => {
+                        slice[count] = c;
+                        count += 1;
+                    }
+                    None => break,
+                }
+            }
+            count as isize
         }
+        _ => -EBADF,
     }
 }
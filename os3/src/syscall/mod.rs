@@ -1,33 +1,80 @@
 // 系统调用实现
 
 // 调用种类对应的ID
+const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_SLEEP: usize = 101;
 const SYSCALL_YIELD: usize = 124;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_TIMES: usize = 153;
 const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_SPAWN: usize = 400;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_TRACE_DUMP: usize = 411;
+const SYSCALL_SET_LOG_LEVEL: usize = 412;
+const SYSCALL_TASK_INFO_OF: usize = 413;
+const SYSCALL_TASK_LIST: usize = 414;
+const SYSCALL_SET_TIME_SLICE: usize = 415;
 
+pub mod errno; // 错误码
 mod fs; // 字符读写相关的系统调用
 pub mod process; //文件读写相关的系统调用 
 
+use errno::*;
 use fs::*;
 use process::*;
 
-use crate::task::update_syscall_times;
+use crate::config::{KILL_ON_SYSCALL_ERROR, MAX_SYSCALL_NUM};
+use crate::loader::get_app_name;
+use crate::trace::{trace, TraceKind};
+use crate::task::{current_app_id, exit_current_and_run_next, update_syscall_times, ExitReason};
 
 // syscall 函数并不会实际处理系统调用，而只是根据 syscall ID 分发到具体的处理函数
 // trap_handler从上下文中取出a7作为syscall_id，取出a0~a2作为参数调用它
 pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
     // LAB1: You may need to update syscall info here.
-    // 新增：记录系统调用次数
-    update_syscall_times(syscall_id);
+    // 新增：记录系统调用次数，超出范围的ID不记录
+    if syscall_id < MAX_SYSCALL_NUM {
+        update_syscall_times(syscall_id);
+    }
 
-    match syscall_id {
+    trace(TraceKind::Syscall, &[syscall_id, args[0], args[1], args[2]]);
+    let ret = match syscall_id {
+        SYSCALL_READ => sys_read(args[0], args[1] as *mut u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_SLEEP => sys_sleep(args[0]),
         SYSCALL_YIELD => sys_yield(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_TIMES => sys_times(args[0] as *mut TaskTimes),
         SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
-        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo, args[1]),
+        SYSCALL_TRACE_DUMP => sys_trace_dump(),
+        SYSCALL_SET_LOG_LEVEL => sys_set_log_level(args[0] as isize),
+        SYSCALL_TASK_INFO_OF => sys_task_info_of(args[0], args[1] as *mut TaskInfoEx),
+        SYSCALL_TASK_LIST => sys_task_list(args[0] as *mut TaskListEntry, args[1]),
+        SYSCALL_SET_TIME_SLICE => sys_set_time_slice(args[0]),
+        _ => {
+            warn!("[kernel] Unsupported syscall_id: {}", syscall_id);
+            -ENOSYS
+        }
+    };
+
+    // 按内核策略决定是否直接杀死传入坏参数的应用
+    if KILL_ON_SYSCALL_ERROR && [-ENOSYS, -EBADF, -EFAULT].contains(&ret) {
+        error!(
+            "[kernel] Bad syscall {} returned {} in application {}, killed.",
+            syscall_id,
+            ret,
+            get_app_name(current_app_id())
+        );
+        exit_current_and_run_next(ExitReason::BadSyscall {
+            syscall_id,
+            errno: -ret,
+        });
     }
+    trace(TraceKind::SyscallRet, &[syscall_id, ret as usize]);
+    ret
 }
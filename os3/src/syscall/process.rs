@@ -1,8 +1,17 @@
 // 任务管理相关的系统调用
 
 use crate::config::MAX_SYSCALL_NUM;
-use crate::task::{exit_current_and_run_next, suspend_current_and_run_next, get_task_info, TaskStatus}; // 新增get_task_info
-use crate::timer::get_time_us;
+use super::errno::{EBUSY, EFAULT, EINVAL, ENOENT, ENOMEM, ESRCH};
+use crate::logging::{level_filter, set_level_override};
+use crate::trace::dump_trace;
+use crate::loader::{check_user_ptr, check_user_range, get_app_id_by_name, get_app_name, read_user_str};
+use crate::task::{
+    current_app_id, current_task_id, exit_current_and_run_next, get_task_info, get_task_info_of,
+    get_task_stats, get_task_stats_of, get_task_times, set_current_priority,
+    set_current_time_slice, sleep_current_and_run_next, spawn, suspend_current_and_run_next,
+    task_list, ExitReason, SpawnError, TaskStatus,
+};
+use crate::timer::{add_timer, get_time_ms, get_time_us, set_next_trigger};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -11,16 +20,78 @@ pub struct TimeVal {
     pub usec: usize, // 微秒
 }
 
+// 任务在用户态和内核态分别运行的时间，单位为微秒
+#[repr(C)]
+#[derive(Debug)]
+pub struct TaskTimes {
+    pub utime: usize, // 用户态时间
+    pub stime: usize, // 内核态时间
+}
+
 pub struct TaskInfo {
     pub status: TaskStatus,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
     pub time: usize,
 }
 
+// sys_task_info的版本，版本0只返回TaskInfo，与ch3_taskinfo等已有的测试兼容
+const TASK_INFO_V0: usize = 0;
+// 版本1返回TaskInfoEx，开头就是TaskInfo，后面追加调度统计
+const TASK_INFO_V1: usize = 1;
+
+// 任务的调度统计，时间单位为微秒
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct TaskStats {
+    pub voluntary_switches: usize, // 主动让出CPU的次数
+    pub preemptions: usize, // 被时钟中断抢占的次数
+    pub scheduled: usize, // 被调度运行的次数
+    pub ready_time: usize, // 在就绪队列中等待的累计时间
+    pub exit_code: isize, // 退出码，任务结束后才有意义，被内核杀死时为-1
+}
+
+#[repr(C)]
+pub struct TaskInfoEx {
+    pub info: TaskInfo,
+    pub stats: TaskStats,
+}
+
+// 应用名最多保留的字节数，更长的会被截断
+const TASK_NAME_LEN: usize = 32;
+
+// sys_task_list中任务状态的取值，TaskStatus是Rust布局的枚举，不直接放进与用户程序共享的结构体
+pub const TASK_STATUS_UNINIT: usize = 0;
+pub const TASK_STATUS_READY: usize = 1;
+pub const TASK_STATUS_RUNNING: usize = 2;
+pub const TASK_STATUS_EXITED: usize = 3;
+pub const TASK_STATUS_SLEEPING: usize = 4;
+
+fn task_status_code(status: TaskStatus) -> usize {
+    match status {
+        TaskStatus::UnInit => TASK_STATUS_UNINIT,
+        TaskStatus::Ready => TASK_STATUS_READY,
+        TaskStatus::Running => TASK_STATUS_RUNNING,
+        TaskStatus::Exited => TASK_STATUS_EXITED,
+        TaskStatus::Sleeping => TASK_STATUS_SLEEPING,
+    }
+}
+
+// sys_task_list返回的一项
+#[repr(C)]
+pub struct TaskListEntry {
+    pub id: usize,
+    pub status: usize, // 任务状态，取值为上面的TASK_STATUS_*
+    pub name: [u8; TASK_NAME_LEN], // 应用名，不足的部分填'\0'
+}
+
 // 任务退出并提交退出代码
 pub fn sys_exit(exit_code: i32) -> ! {
-    info!("[kernel] Application exited with code {}", exit_code);
-    exit_current_and_run_next();
+    info!(
+        "[kernel] Application {} exited with code {}",
+        get_app_name(current_app_id()),
+        exit_code
+    );
+    exit_current_and_run_next(ExitReason::Exited(exit_code));
     panic!("Unreachable in sys_exit!");
 }
 
@@ -30,8 +101,49 @@ pub fn sys_yield() -> isize {
     0
 }
 
+// 单次睡眠时长的上限，单位毫秒，超过时返回-EINVAL
+const MAX_SLEEP_MS: usize = 24 * 60 * 60 * 1000;
+
+// 睡眠指定的毫秒数，期间任务不参与调度，到期后由时钟中断唤醒
+pub fn sys_sleep(ms: usize) -> isize {
+    if ms > MAX_SLEEP_MS {
+        return -EINVAL;
+    }
+    add_timer(get_time_ms().saturating_add(ms), current_task_id());
+    // 新定时器可能比已设置的时钟中断更早到期，重新设置一次
+    set_next_trigger();
+    sleep_current_and_run_next();
+    0
+}
+
+// 设置当前任务的优先级，优先级至少为2，成功时返回设置的优先级
+// 失败时按测试用例ch5_setprio的约定返回-1，而不是-EINVAL
+pub fn sys_set_priority(prio: isize) -> isize {
+    if prio < 2 {
+        return -1;
+    }
+    set_current_priority(prio as usize);
+    prio
+}
+
+// 时间片长度的上限，单位毫秒
+const MAX_TIME_SLICE_MS: usize = 1000;
+
+// 设置当前任务每次被调度后最多连续运行的毫秒数，从下一次被调度开始生效
+pub fn sys_set_time_slice(ms: usize) -> isize {
+    if !(1..=MAX_TIME_SLICE_MS).contains(&ms) {
+        return -EINVAL;
+    }
+    set_current_time_slice(ms * 1000);
+    0
+}
+
 // 获取时间
 pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
+    // 指针不在当前应用的地址范围内，返回EFAULT
+    if !check_user_ptr(current_app_id(), current_task_id(), ts) {
+        return -EFAULT;
+    }
     let us = get_time_us();
     unsafe {
         *ts = TimeVal {
@@ -43,7 +155,118 @@ pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
 }
 
 /// YOUR JOB: Finish sys_task_info to pass testcases
-pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
-    unsafe { *ti = get_task_info(); }
+// 第二个参数是版本号，原来只传一个参数的调用者在a1中传的是0，得到的还是TaskInfo
+pub fn sys_task_info(ti: *mut TaskInfo, version: usize) -> isize {
+    match version {
+        TASK_INFO_V0 => {
+            if !check_user_ptr(current_app_id(), current_task_id(), ti) {
+                return -EFAULT;
+            }
+            unsafe { *ti = get_task_info(); }
+        }
+        TASK_INFO_V1 => {
+            let ti = ti as *mut TaskInfoEx;
+            if !check_user_ptr(current_app_id(), current_task_id(), ti) {
+                return -EFAULT;
+            }
+            unsafe {
+                *ti = TaskInfoEx {
+                    info: get_task_info(),
+                    stats: get_task_stats(),
+                };
+            }
+        }
+        _ => return -EINVAL,
+    }
+    0
+}
+
+// 获取task_id对应任务的信息和调度统计，格式与版本1的sys_task_info相同
+// 任务不存在时返回-ESRCH
+pub fn sys_task_info_of(task_id: usize, ti: *mut TaskInfoEx) -> isize {
+    if !check_user_ptr(current_app_id(), current_task_id(), ti) {
+        return -EFAULT;
+    }
+    match (get_task_info_of(task_id), get_task_stats_of(task_id)) {
+        (Some(info), Some(stats)) => {
+            unsafe { *ti = TaskInfoEx { info, stats }; }
+            0
+        }
+        _ => -ESRCH,
+    }
+}
+
+// 把任务表中的任务依次填入长度为len的数组buf，返回任务总数，总数大于len时只填前len个
+pub fn sys_task_list(buf: *mut TaskListEntry, len: usize) -> isize {
+    let size = match len.checked_mul(core::mem::size_of::<TaskListEntry>()) {
+        Some(size) => size,
+        None => return -EFAULT,
+    };
+    if buf as usize % core::mem::align_of::<TaskListEntry>() != 0
+        || !check_user_range(current_app_id(), current_task_id(), buf as usize, size, true)
+    {
+        return -EFAULT;
+    }
+    let tasks = task_list();
+    for (id, &(app_id, status)) in tasks.iter().enumerate().take(len) {
+        // 没有使用过的位置没有对应的应用，名字留空
+        let mut name = [0; TASK_NAME_LEN];
+        if status != TaskStatus::UnInit {
+            let app_name = get_app_name(app_id).as_bytes();
+            let n = app_name.len().min(TASK_NAME_LEN);
+            name[..n].copy_from_slice(&app_name[..n]);
+        }
+        unsafe {
+            *buf.add(id) = TaskListEntry {
+                id,
+                status: task_status_code(status),
+                name,
+            };
+        }
+    }
+    tasks.len() as isize
+}
+
+// 获取当前任务在用户态和内核态各运行了多久，不包括等待其他任务运行的时间
+pub fn sys_times(tms: *mut TaskTimes) -> isize {
+    if !check_user_ptr(current_app_id(), current_task_id(), tms) {
+        return -EFAULT;
+    }
+    unsafe { *tms = get_task_times(); }
+    0
+}
+
+// 按名字启动内嵌在内核中的应用，返回新任务的ID
+// 路径不可读返回-EFAULT，找不到应用返回-ENOENT，该应用已经在运行返回-EBUSY，内核堆不足返回-ENOMEM
+pub fn sys_spawn(path: *const u8) -> isize {
+    let name = match read_user_str(current_app_id(), current_task_id(), path) {
+        Some(name) => name,
+        None => return -EFAULT,
+    };
+    let app_id = match get_app_id_by_name(name) {
+        Some(app_id) => app_id,
+        None => return -ENOENT,
+    };
+    match spawn(app_id) {
+        Ok(id) => id as isize,
+        Err(SpawnError::NoSuchApp) => -ENOENT,
+        Err(SpawnError::AlreadyRunning) => -EBUSY,
+        Err(SpawnError::NoMemory) => -ENOMEM,
+    }
+}
+
+// 把内核事件跟踪缓冲区输出到控制台，返回输出的记录数
+pub fn sys_trace_dump() -> isize {
+    dump_trace() as isize
+}
+
+// 运行时设置内核日志级别，0到5依次为OFF、ERROR、WARN、INFO、DEBUG、TRACE，对所有模块生效
+// 传入负数时恢复编译时LOG设置的规则
+pub fn sys_set_log_level(level: isize) -> isize {
+    match level {
+        0..=5 => set_level_override(Some(level_filter(level as usize))),
+        _ if level < 0 => set_level_override(None),
+        _ => return -EINVAL,
+    }
     0
 }
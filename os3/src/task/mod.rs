@@ -1,29 +1,51 @@
 mod context;
+mod scheduler;
 mod switch;
 #[allow(clippy::module_inception)]
 mod task;
 
-use crate::config::{MAX_APP_NUM, MAX_SYSCALL_NUM};
-use crate::loader::{get_num_app, init_app_cx};
+use crate::loader::{
+    find_stack_overflow, get_app_name, get_app_region, get_num_app, init_app_cx, load_app,
+    stack_high_water, StackKind,
+};
+use crate::config::{KERNEL_STACK_SIZE, MAX_APP_NUM, USER_STACK_SIZE};
+use crate::console::{flush, handle_interrupt};
+use crate::logging::set_current_task;
+use crate::sbi::shutdown;
 use crate::sync::UPSafeCell;
-use crate::syscall::process::TaskInfo; // 新增
-use crate::timer::get_time_us;
+use crate::syscall::process::{TaskInfo, TaskStats, TaskTimes}; // 新增
+use crate::timer::{check_timer, get_time_us, set_next_trigger, set_time_slice};
+use crate::trace::{trace, TraceKind};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use lazy_static::*;
+use scheduler::{new_scheduler, Scheduler};
 pub use switch::__switch;
-pub use task::{TaskControlBlock, TaskStatus};
+pub use task::{ExitReason, TaskControlBlock, TaskStatus};
+use task::pass_lt;
 
 pub use context::TaskContext;
 
 // 任务表，初始化后本体不变，使用UPSafeCell实现内部可变
 pub struct TaskManager {
-    num_app: usize, // 任务总数
     inner: UPSafeCell<TaskManagerInner>, // 可变部分
 }
 
+// 启动新任务失败的原因
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpawnError {
+    NoSuchApp, // 应用不存在或无法加载
+    AlreadyRunning, // 该应用已经有一个任务在运行
+    NoMemory, // 内核堆不足，任务表或栈分配失败
+}
+
 // 任务表可变部分
 struct TaskManagerInner {
-    tasks: [TaskControlBlock; MAX_APP_NUM], // 各个任务的信息
+    tasks: Vec<TaskControlBlock>, // 各个任务的信息，按任务ID索引，启动新任务时按需增长
     current_task: usize, // 当前正在执行哪个任务
+    scheduler: Box<dyn Scheduler>, // 调度策略
+    start_time: usize, // 第一个任务开始运行的时刻（微秒）
+    idle_time: usize, // 没有任务可运行、处于空闲状态的累计时间（微秒）
 }
 
 // lazy_static! 宏提供了全局变量的运行时初始化功能。
@@ -37,15 +59,19 @@ lazy_static! {
 
         // 获取应用总数
         let num_app = get_num_app();
+        assert!(
+            num_app <= MAX_APP_NUM,
+            "[kernel] {} applications exceed MAX_APP_NUM ({}), the kernel heap cannot hold their stacks",
+            num_app,
+            MAX_APP_NUM
+        );
+
+        // 构造任务表，开机时每个应用各启动一个任务，任务ID和应用ID相同
+        let mut tasks = Vec::new();
+        tasks.resize(num_app, TaskControlBlock::empty());
 
-        // 构造任务表
-        let mut tasks = [TaskControlBlock {
-            task_cx: TaskContext::zero_init(),
-            task_status: TaskStatus::UnInit,
-            // 新增
-            task_syscall_times: [0; MAX_SYSCALL_NUM],
-            task_first_running_time: None,
-        }; MAX_APP_NUM];
+        // 构造调度器，就绪的任务都要告知它
+        let mut scheduler = new_scheduler();
 
         // 启动各个任务，初始化到挂起状态
         for (i, t) in tasks.iter_mut().enumerate().take(num_app) {
@@ -55,17 +81,31 @@ lazy_static! {
             // init_app_cx是压Trap上下文进内核栈，返回新的栈顶
             // goto_restore是在压Trap上下文进内核栈的基础上，构建任务切换的上下文结构体，不压栈而是直接返回
             // 这俩上下文创造函数套一起，返回的是任务上下文结构体，刚好放进t的任务上下
-            t.task_cx = TaskContext::goto_restore(init_app_cx(i));
-            t.task_status = TaskStatus::Ready;
+            // 不能加载的应用留在UnInit状态，不会被调度
+            if get_app_region(i).is_none() {
+                continue;
+            }
+            let kstack_ptr = init_app_cx(i, i).unwrap_or_else(|| {
+                panic!(
+                    "[kernel] Out of kernel heap while creating task {} of {}, increase KERNEL_HEAP_SIZE!",
+                    i, num_app
+                )
+            });
+            t.task_cx = TaskContext::goto_restore(kstack_ptr);
+            t.task_app_id = i;
+            t.make_ready(0);
+            scheduler.add(i);
         }
 
         // 封装成任务表返回
         TaskManager {
-            num_app,
             inner: unsafe {
                 UPSafeCell::new(TaskManagerInner {
                     tasks,
                     current_task: 0,
+                    scheduler,
+                    start_time: 0,
+                    idle_time: 0,
                 })
             },
         }
@@ -78,10 +118,19 @@ impl TaskManager {
     fn run_first_task(&self) -> ! {
         // 获取任务表可变部分的一个独占借用（也就是可变借用）给inner
         let mut inner = self.inner.exclusive_access();
-        // 从中取出第一个任务
-        let task0 = &mut inner.tasks[0];
-        // 状态设置为正在运行
-        task0.task_status = TaskStatus::Running;
+        // 由调度器选出第一个任务
+        let first = self.find_next_task(&mut inner).expect("No application to run!");
+        inner.current_task = first;
+        trace(TraceKind::Switch, &[first, first]);
+        set_current_task(first);
+        inner.start_time = get_time_us();
+        let preemptive = inner.scheduler.preemptive();
+        let task0 = &mut inner.tasks[first];
+        // 状态设置为正在运行，在此之前的等待从开机算起
+        task0.make_running(get_time_us());
+        task0.advance_pass();
+        task0.task_last_stamp = get_time_us();
+        set_time_slice(preemptive.then(|| task0.task_time_slice));
 
         // 新增：对初次调度时间则进行设置
         task0.task_first_running_time = Some(get_time_us() / 1000);
@@ -103,44 +152,162 @@ impl TaskManager {
         panic!("unreachable in run_first_task!");
     }
 
-    // 应用状态设置为挂起
-    fn mark_current_suspended(&self) {
+    // 应用状态设置为挂起，preempted区分是被时钟中断抢占还是主动让出
+    fn mark_current_suspended(&self, preempted: bool) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.tasks[current].task_status = TaskStatus::Ready;
+        let task = &mut inner.tasks[current];
+        task.make_ready(get_time_us());
+        if preempted {
+            task.task_preemptions += 1;
+        } else {
+            task.task_voluntary_switches += 1;
+        }
+        inner.scheduler.add(current);
     }
 
-    // 应用状态设置为已结束
-    fn mark_current_exited(&self) {
+    // 应用状态设置为已结束，并记录退出原因
+    fn mark_current_exited(&self, reason: ExitReason) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
         inner.tasks[current].task_status = TaskStatus::Exited;
+        inner.tasks[current].task_exit_reason = Some(reason);
+        let (kernel_used, user_used) = stack_high_water(current);
+        info!(
+            "[kernel] Task {} ({}) stack high-water: kernel {}/{} bytes, user {}/{} bytes",
+            current,
+            get_app_name(inner.tasks[current].task_app_id),
+            kernel_used,
+            KERNEL_STACK_SIZE,
+            user_used,
+            USER_STACK_SIZE
+        );
     }
 
-    // 寻找下一个挂起的应用，从当前的ID顺延查找
-    fn find_next_task(&self) -> Option<usize> {
-        let inner = self.inner.exclusive_access();
+    // 应用状态设置为睡眠
+    fn mark_current_sleeping(&self) {
+        let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        (current + 1..current + self.num_app + 1)
-            .map(|id| id % self.num_app)
-            .find(|id| inner.tasks[*id].task_status == TaskStatus::Ready)
+        inner.tasks[current].task_status = TaskStatus::Sleeping;
+        inner.tasks[current].task_voluntary_switches += 1;
+    }
+
+    // 唤醒睡眠中的应用，重新交给调度器
+    fn wakeup_task(&self, id: usize) {
+        let mut inner = self.inner.exclusive_access();
+        if inner.tasks[id].task_status == TaskStatus::Sleeping {
+            // 睡眠期间其他任务的行程一直在前进，醒来时追上其中最小的行程，
+            // 否则在步长调度下它会连续运行很多个时间片，行程差过大时pass_lt的比较也会出错
+            let min_pass = inner
+                .tasks
+                .iter()
+                .filter(|t| matches!(t.task_status, TaskStatus::Ready | TaskStatus::Running))
+                .map(|t| t.task_pass)
+                .reduce(|a, b| if pass_lt(b, a) { b } else { a });
+            if let Some(min_pass) = min_pass {
+                if pass_lt(inner.tasks[id].task_pass, min_pass) {
+                    inner.tasks[id].task_pass = min_pass;
+                }
+            }
+            inner.tasks[id].make_ready(get_time_us());
+            inner.scheduler.add(id);
+        }
+    }
+
+    // 是否还有挂起的应用
+    fn has_ready_task(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        inner.tasks.iter().any(|t| t.task_status == TaskStatus::Ready)
+    }
+
+    // 获取当前任务的ID
+    fn current_task_id(&self) -> usize {
+        self.inner.exclusive_access().current_task
+    }
+
+    // 获取当前任务运行的应用的ID
+    fn current_app_id(&self) -> usize {
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].task_app_id
+    }
+
+    // 启动app_id对应的应用，优先复用已结束任务的位置，没有时扩充任务表，返回新任务的ID
+    // 应用都链接在各自固定的地址上，所以同一个应用同时只能运行一份
+    // 堆空间不足时返回None
+    fn spawn(&self, app_id: usize) -> Result<usize, SpawnError> {
+        // 不能加载的应用不允许启动
+        get_app_region(app_id).ok_or(SpawnError::NoSuchApp)?;
+        let mut inner = self.inner.exclusive_access();
+        if inner.tasks.iter().any(|t| t.is_alive() && t.task_app_id == app_id) {
+            return Err(SpawnError::AlreadyRunning);
+        }
+        let id = match inner.tasks.iter().position(|t| !t.is_alive()) {
+            Some(id) => id,
+            None => {
+                inner.tasks.try_reserve(1).map_err(|_| SpawnError::NoMemory)?;
+                inner.tasks.push(TaskControlBlock::empty());
+                inner.tasks.len() - 1
+            }
+        };
+        let kstack_ptr = match init_app_cx(app_id, id) {
+            Some(kstack_ptr) => kstack_ptr,
+            None => {
+                warn!("[kernel] Out of kernel heap while spawning app {}", app_id);
+                return Err(SpawnError::NoMemory);
+            }
+        };
+        // 上一次运行可能改写了应用的数据段，重新加载一遍
+        load_app(app_id);
+        // 新任务从当前任务的行程开始，避免在步长调度下长时间独占CPU
+        let pass = inner.tasks[inner.current_task].task_pass;
+        let task = &mut inner.tasks[id];
+        *task = TaskControlBlock::empty();
+        task.task_cx = TaskContext::goto_restore(kstack_ptr);
+        task.task_app_id = app_id;
+        task.task_pass = pass;
+        task.make_ready(get_time_us());
+        inner.scheduler.add(id);
+        Ok(id)
+    }
+
+    // 寻找下一个挂起的应用，具体选哪个由调度器决定
+    fn find_next_task(&self, inner: &mut TaskManagerInner) -> Option<usize> {
+        inner.scheduler.fetch(&inner.tasks)
     }
 
     // 切换到下一个任务
     fn run_next_task(&self) {
+        let mut inner = self.inner.exclusive_access();
         // 先寻找还有没有挂起的任务
-        if let Some(next) = self.find_next_task() {
+        if let Some(next) = self.find_next_task(&mut inner) {
             // 类似应用首次运行的过程，不过不用创造空任务了
-            let mut inner = self.inner.exclusive_access();
             let current = inner.current_task;
-            inner.tasks[next].task_status = TaskStatus::Running;
+            let now = get_time_us();
+            inner.tasks[next].make_running(now);
+            inner.tasks[next].advance_pass();
+
+            // 切换前结算当前任务的内核态时间，下一个任务从现在开始计时
+            inner.tasks[current].account_kernel_time(now);
+            inner.tasks[next].task_last_stamp = now;
 
             // 新增：如果没有被调度过，则对初次调度时间则进行设置
             if inner.tasks[next].task_first_running_time == None {
                 inner.tasks[next].task_first_running_time = Some(get_time_us() / 1000);
             }
 
+            // 离开当前任务前检查它的内核栈
+            // panic路径还要访问任务表，先释放借用
+            if find_stack_overflow(current) == Some(StackKind::Kernel) {
+                let app_id = inner.tasks[current].task_app_id;
+                drop(inner);
+                kernel_stack_overflow(current, app_id);
+            }
+
             inner.current_task = next;
+            trace(TraceKind::Switch, &[current, next]);
+            set_current_task(next);
+            let slice = inner.tasks[next].task_time_slice;
+            set_time_slice(inner.scheduler.preemptive().then(|| slice));
             let current_task_cx_ptr = &mut inner.tasks[current].task_cx as *mut TaskContext;
             let next_task_cx_ptr = &inner.tasks[next].task_cx as *const TaskContext;
             drop(inner);
@@ -148,12 +315,66 @@ impl TaskManager {
                 __switch(current_task_cx_ptr, next_task_cx_ptr);
             }
 
-        // 没有挂起的任务了，全部运行完毕
+        // 没有挂起的任务，但还有任务在睡眠，进入空闲状态等它们就绪
+        } else if inner.tasks.iter().any(|t| t.task_status == TaskStatus::Sleeping) {
+            drop(inner);
+            self.idle();
+            self.run_next_task();
+
+        // 所有任务都已结束，报告CPU利用率后关机，有应用意外被内核杀死时以失败状态关机
         } else {
-            panic!("All applications completed!");
+            let total = get_time_us() - inner.start_time;
+            let busy = total - inner.idle_time;
+            println!(
+                "[kernel] All applications completed! busy {} ms, idle {} ms, CPU utilisation {}%",
+                busy / 1000,
+                inner.idle_time / 1000,
+                busy * 100 / total.max(1)
+            );
+            let killed = inner
+                .tasks
+                .iter()
+                .filter(|t| t.task_exit_reason.map_or(false, |reason| reason.killed()))
+                .filter(|t| !fault_expected(t))
+                .count();
+            if killed > 0 {
+                println!("[kernel] {} application(s) were unexpectedly killed by the kernel", killed);
+            }
+            drop(inner);
+            flush();
+            shutdown(killed > 0);
         }
     }
 
+    // 空闲循环，直到有任务就绪
+    // __alltraps只能处理来自U态的陷入，所以这里不打开sstatus.SIE，
+    // 而是利用wfi的特性：只要sie中允许的中断处于等待状态，即使全局中断关闭，wfi也会返回
+    fn idle(&self) {
+        let start = get_time_us();
+        // 没有任务在运行，不需要时间片，免得已经过期的时间片让时钟中断一直处于等待状态
+        set_time_slice(None);
+        while !self.has_ready_task() {
+            unsafe {
+                core::arch::asm!("wfi");
+            }
+            // 醒来后先处理可能等待中的外部中断，再唤醒到期的睡眠任务，并重新设置时钟以清除等待中的时钟中断
+            handle_interrupt();
+            check_timer();
+            set_next_trigger();
+        }
+        let now = get_time_us();
+        let mut inner = self.inner.exclusive_access();
+        inner.idle_time += now - start;
+        // 空闲时间不计入任何任务
+        let current = inner.current_task;
+        inner.tasks[current].task_last_stamp = now;
+    }
+
+    // 当前调度策略是否允许时钟中断抢占
+    fn is_preemptive(&self) -> bool {
+        self.inner.exclusive_access().scheduler.preemptive()
+    }
+
     // LAB1: Try to implement your function to update or get task info!
 
     // 增加对应ID的系统调用计数
@@ -163,17 +384,83 @@ impl TaskManager {
         inner.tasks[current].task_syscall_times[syscall_id] += 1;
     }
 
-    // 获取当前应用任务信息
-    fn get_task_info(&self) -> TaskInfo {
-        let inner = self.inner.exclusive_access();
+    // 从用户态陷入内核，结算当前应用的用户态时间
+    fn account_trap_enter(&self) {
+        let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        let time = get_time_us() / 1000 - inner.tasks[current].task_first_running_time.unwrap();
-        TaskInfo {
-            status: inner.tasks[current].task_status,
-            syscall_times: inner.tasks[current].task_syscall_times,
-            time,
+        inner.tasks[current].account_user_time(get_time_us());
+    }
+
+    // 即将返回用户态，结算当前应用的内核态时间
+    fn account_trap_exit(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].account_kernel_time(get_time_us());
+    }
+
+    // 获取当前应用在用户态和内核态各运行了多久
+    fn get_task_times(&self) -> TaskTimes {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        // 先把本次系统调用到目前为止的时间算进去
+        inner.tasks[current].account_kernel_time(get_time_us());
+        TaskTimes {
+            utime: inner.tasks[current].task_user_time,
+            stime: inner.tasks[current].task_kernel_time,
         }
     }
+
+    // 设置当前应用的优先级
+    fn set_current_priority(&self, prio: usize) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].set_priority(prio);
+    }
+
+    // 设置当前应用的时间片长度，从下一次被调度开始生效
+    fn set_current_time_slice(&self, slice_us: usize) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].task_time_slice = slice_us;
+    }
+
+    // 获取任务ID为id的任务信息
+    // 任务ID超出范围或者对应位置还没有使用过时返回None，还没被调度过的任务time为0
+    fn get_task_info(&self, id: usize) -> Option<TaskInfo> {
+        let inner = self.inner.exclusive_access();
+        let task = inner.tasks.get(id).filter(|t| t.task_status != TaskStatus::UnInit)?;
+        let time = task
+            .task_first_running_time
+            .map_or(0, |first| get_time_us() / 1000 - first);
+        Some(TaskInfo {
+            status: task.task_status,
+            syscall_times: task.task_syscall_times,
+            time,
+        })
+    }
+
+    // 获取任务ID为id的任务的调度统计，返回None的情况同上
+    fn get_task_stats(&self, id: usize) -> Option<TaskStats> {
+        let inner = self.inner.exclusive_access();
+        let task = inner.tasks.get(id).filter(|t| t.task_status != TaskStatus::UnInit)?;
+        Some(TaskStats {
+            voluntary_switches: task.task_voluntary_switches,
+            preemptions: task.task_preemptions,
+            scheduled: task.task_scheduled,
+            ready_time: task.task_ready_time,
+            exit_code: task.task_exit_reason.map_or(0, |reason| reason.exit_code() as isize),
+        })
+    }
+
+    // 任务表中每个位置运行的应用和状态，按任务ID排列
+    fn task_list(&self) -> Vec<(usize, TaskStatus)> {
+        let inner = self.inner.exclusive_access();
+        inner
+            .tasks
+            .iter()
+            .map(|t| (t.task_app_id, t.task_status))
+            .collect()
+    }
 }
 
 // 留给main函数调用的接口
@@ -187,28 +474,114 @@ fn run_next_task() {
     TASK_MANAGER.run_next_task();
 }
 
-fn mark_current_suspended() {
-    TASK_MANAGER.mark_current_suspended();
+fn mark_current_suspended(preempted: bool) {
+    TASK_MANAGER.mark_current_suspended(preempted);
 }
 
-fn mark_current_exited() {
-    TASK_MANAGER.mark_current_exited();
+fn mark_current_exited(reason: ExitReason) {
+    TASK_MANAGER.mark_current_exited(reason);
+}
+
+fn mark_current_sleeping() {
+    TASK_MANAGER.mark_current_sleeping();
 }
 
 // 这些是给外界调用的接口
 
-// 当前应用挂起，运行下一个应用
+// 当前应用主动让出CPU，运行下一个应用
 pub fn suspend_current_and_run_next() {
-    mark_current_suspended();
+    mark_current_suspended(false);
+    run_next_task();
+}
+
+// 当前应用被时钟中断抢占，运行下一个应用
+pub fn preempt_current_and_run_next() {
+    mark_current_suspended(true);
     run_next_task();
 }
 
 // 当前应用退出，运行下一个应用
-pub fn exit_current_and_run_next() {
-    mark_current_exited();
+pub fn exit_current_and_run_next(reason: ExitReason) {
+    mark_current_exited(reason);
+    run_next_task();
+}
+
+// 内核栈溢出时相邻的内存已经被破坏，无法恢复，只能panic
+fn kernel_stack_overflow(task_id: usize, app_id: usize) -> ! {
+    panic!(
+        "Stack overflow in task {} ({}): kernel stack canary clobbered",
+        task_id,
+        get_app_name(app_id)
+    );
+}
+
+// 预期会触发异常的应用，编译时由EXPECTED_FAULTS给出，格式为 应用名:scause,...，在build.rs中检查
+// 例如 EXPECTED_FAULTS=ch2b_bad_address:7 make run，Makefile中默认列出了ch2b_bad_*测试
+static EXPECTED_FAULTS: Option<&str> = option_env!("EXPECTED_FAULTS");
+
+// 只有列出的应用以列出的异常被杀死才不算失败，以其他原因被杀死仍然算失败
+fn fault_expected(task: &TaskControlBlock) -> bool {
+    let scause = match task.task_exit_reason {
+        Some(ExitReason::Fault { scause, .. }) => scause,
+        _ => return false,
+    };
+    let name = get_app_name(task.task_app_id);
+    EXPECTED_FAULTS
+        .unwrap_or("")
+        .split(',')
+        .filter_map(|item| item.split_once(':'))
+        .any(|(app, cause)| app == name && cause.parse() == Ok(scause))
+}
+
+// 检查当前任务的栈底保护字，每次陷入内核时调用
+// 用户栈溢出只杀死出错的应用，内核栈溢出直接panic
+pub fn check_current_stack() {
+    let task_id = current_task_id();
+    match find_stack_overflow(task_id) {
+        Some(StackKind::Kernel) => kernel_stack_overflow(task_id, current_app_id()),
+        Some(StackKind::User) => {
+            println!(
+                "[kernel] Stack overflow in task {} ({}): user stack canary clobbered, killed.",
+                task_id,
+                get_app_name(current_app_id())
+            );
+            exit_current_and_run_next(ExitReason::StackOverflow);
+        }
+        None => {}
+    }
+}
+
+// 当前应用进入睡眠，运行下一个应用，由定时器负责唤醒
+pub fn sleep_current_and_run_next() {
+    mark_current_sleeping();
     run_next_task();
 }
 
+// 唤醒睡眠中的应用
+pub fn wakeup_task(id: usize) {
+    TASK_MANAGER.wakeup_task(id);
+}
+
+// 获取当前任务的ID
+pub fn current_task_id() -> usize {
+    TASK_MANAGER.current_task_id()
+}
+
+// 获取当前任务运行的应用的ID
+pub fn current_app_id() -> usize {
+    TASK_MANAGER.current_app_id()
+}
+
+// 启动一个新任务运行app_id对应的应用
+pub fn spawn(app_id: usize) -> Result<usize, SpawnError> {
+    TASK_MANAGER.spawn(app_id)
+}
+
+// 时钟中断到来时是否应该切换任务
+pub fn is_preemptive() -> bool {
+    TASK_MANAGER.is_preemptive()
+}
+
 // LAB1: Public functions implemented here provide interfaces.
 // You may use TASK_MANAGER member functions to handle requests.
 
@@ -217,7 +590,52 @@ pub fn update_syscall_times(syscall_id: usize) {
     TASK_MANAGER.update_syscall_times(syscall_id);
 }
 
+// 陷入内核时计时
+pub fn account_trap_enter() {
+    TASK_MANAGER.account_trap_enter();
+}
+
+// 返回用户态前计时
+pub fn account_trap_exit() {
+    TASK_MANAGER.account_trap_exit();
+}
+
+// 获取当前应用的用户态和内核态时间
+pub fn get_task_times() -> TaskTimes {
+    TASK_MANAGER.get_task_times()
+}
+
+// 设置当前应用的优先级
+pub fn set_current_priority(prio: usize) {
+    TASK_MANAGER.set_current_priority(prio);
+}
+
+// 设置当前应用的时间片长度
+pub fn set_current_time_slice(slice_us: usize) {
+    TASK_MANAGER.set_current_time_slice(slice_us);
+}
+
 // 获取当前应用任务信息
 pub fn get_task_info() -> TaskInfo {
-    TASK_MANAGER.get_task_info()
-}
\ No newline at end of file
+    TASK_MANAGER.get_task_info(current_task_id()).unwrap()
+}
+
+// 获取当前应用的调度统计
+pub fn get_task_stats() -> TaskStats {
+    TASK_MANAGER.get_task_stats(current_task_id()).unwrap()
+}
+
+// 获取任意任务的信息
+pub fn get_task_info_of(id: usize) -> Option<TaskInfo> {
+    TASK_MANAGER.get_task_info(id)
+}
+
+// 获取任意任务的调度统计
+pub fn get_task_stats_of(id: usize) -> Option<TaskStats> {
+    TASK_MANAGER.get_task_stats(id)
+}
+
+// 列出任务表中的所有任务
+pub fn task_list() -> Vec<(usize, TaskStatus)> {
+    TASK_MANAGER.task_list()
+}
@@ -0,0 +1,119 @@
+// 可替换的调度策略，TaskManager只负责维护任务表，选哪个任务运行交给这里的Scheduler决定
+// 编译时通过环境变量SCHED选择策略，例如 SCHED=STRIDE make run，未设置时使用轮转调度
+
+use super::task::pass_lt;
+use super::{TaskControlBlock, TaskStatus};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use core::cmp::Ordering;
+
+// 调度器特征，所有调度策略都实现它
+pub trait Scheduler: Send {
+    // 有任务进入就绪态（初始化完成或被挂起）时通知调度器
+    fn add(&mut self, id: usize);
+    // 从任务表中选出下一个要运行的就绪任务，没有就绪任务时返回None
+    fn fetch(&mut self, tasks: &[TaskControlBlock]) -> Option<usize>;
+    // 时钟中断到来时是否抢占当前任务
+    fn preemptive(&self) -> bool {
+        true
+    }
+}
+
+// 轮转调度：从上次选中的任务之后顺延查找第一个就绪任务
+pub struct RoundRobin {
+    cursor: usize, // 下一次从哪里开始找
+}
+
+impl Scheduler for RoundRobin {
+    fn add(&mut self, _id: usize) {}
+
+    fn fetch(&mut self, tasks: &[TaskControlBlock]) -> Option<usize> {
+        let n = tasks.len();
+        let next = (self.cursor..self.cursor + n)
+            .map(|id| id % n)
+            .find(|id| tasks[*id].task_status == TaskStatus::Ready)?;
+        self.cursor = next + 1;
+        Some(next)
+    }
+}
+
+// 先来先服务：按进入就绪态的先后排队，且时钟中断不抢占，任务只在让出或退出时切换
+pub struct Fifo {
+    ready_queue: VecDeque<usize>, // 就绪队列
+}
+
+impl Scheduler for Fifo {
+    fn add(&mut self, id: usize) {
+        self.ready_queue.push_back(id);
+    }
+
+    fn fetch(&mut self, tasks: &[TaskControlBlock]) -> Option<usize> {
+        // 跳过入队后状态又发生变化的任务
+        while let Some(id) = self.ready_queue.pop_front() {
+            if tasks[id].task_status == TaskStatus::Ready {
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    fn preemptive(&self) -> bool {
+        false
+    }
+}
+
+// 静态优先级：总是选优先级数值最大的就绪任务，同优先级之间轮转
+pub struct Priority {
+    cursor: usize,
+}
+
+impl Scheduler for Priority {
+    fn add(&mut self, _id: usize) {}
+
+    fn fetch(&mut self, tasks: &[TaskControlBlock]) -> Option<usize> {
+        let n = tasks.len();
+        // max_by_key遇到相等时取最后一个，所以倒着找，保证同优先级时选离cursor最近的
+        let next = (self.cursor..self.cursor + n)
+            .rev()
+            .map(|id| id % n)
+            .filter(|id| tasks[*id].task_status == TaskStatus::Ready)
+            .max_by_key(|id| tasks[*id].task_priority)?;
+        self.cursor = next + 1;
+        Some(next)
+    }
+}
+
+// 步长调度：选行程task_pass最小的就绪任务，行程由TaskManager在每次调度时推进
+pub struct Stride;
+
+impl Scheduler for Stride {
+    fn add(&mut self, _id: usize) {}
+
+    fn fetch(&mut self, tasks: &[TaskControlBlock]) -> Option<usize> {
+        (0..tasks.len())
+            .filter(|id| tasks[*id].task_status == TaskStatus::Ready)
+            .min_by(|a, b| {
+                let (pa, pb) = (tasks[*a].task_pass, tasks[*b].task_pass);
+                if pass_lt(pa, pb) {
+                    Ordering::Less
+                } else if pass_lt(pb, pa) {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+    }
+}
+
+// 根据编译时的环境变量SCHED构造调度器，取值在build.rs中已经检查过
+pub fn new_scheduler() -> Box<dyn Scheduler> {
+    match option_env!("SCHED") {
+        None | Some("RR") => Box::new(RoundRobin { cursor: 0 }),
+        Some("FIFO") => Box::new(Fifo {
+            ready_queue: VecDeque::new(),
+        }),
+        Some("PRIORITY") => Box::new(Priority { cursor: 0 }),
+        Some("STRIDE") => Box::new(Stride),
+        Some(other) => unreachable!("Unknown SCHED={}", other),
+    }
+}
@@ -1,16 +1,127 @@
 // 任务管理使用的结构，保存每个任务的当前状态信息
 
 use super::TaskContext;
-use super::MAX_SYSCALL_NUM;
+use crate::config::{BIG_STRIDE, DEFAULT_PRIORITY, DEFAULT_TIME_SLICE_US, MAX_SYSCALL_NUM};
 
 #[derive(Copy, Clone)]
 // 每个任务的信息
 pub struct TaskControlBlock {
     pub task_status: TaskStatus, // 任务状态
     pub task_cx: TaskContext, //任务上下文结构体
+    pub task_app_id: usize, // 运行的是哪个应用，同一个应用可以先后被多个任务运行
     // LAB1: Add whatever you need about the Task.
     pub task_syscall_times: [u32; MAX_SYSCALL_NUM], // 各种系统调用的次数
     pub task_first_running_time: Option<usize>, // 任务第一次被调度的时刻
+    pub task_priority: usize, // 优先级，供优先级调度和步长调度使用
+    pub task_stride: usize, // 步长，等于BIG_STRIDE / 优先级
+    pub task_time_slice: usize, // 每次被调度后最多连续运行的时间（微秒）
+    pub task_pass: usize, // 行程，每被调度一次增加一个步长
+    pub task_user_time: usize, // 在用户态运行的累计时间（微秒）
+    pub task_kernel_time: usize, // 在内核态运行的累计时间（微秒）
+    pub task_last_stamp: usize, // 上一次计时的时刻，在陷入、返回用户态和任务切换时更新
+    pub task_voluntary_switches: usize, // 主动让出CPU（yield、sleep、等待输入）的次数
+    pub task_preemptions: usize, // 被时钟中断抢占的次数
+    pub task_scheduled: usize, // 被调度运行的次数
+    pub task_ready_time: usize, // 在就绪队列中等待的累计时间（微秒）
+    pub task_ready_stamp: usize, // 最近一次进入就绪态的时刻
+    pub task_exit_reason: Option<ExitReason>, // 退出原因，任务结束后才有
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+// 任务结束的原因
+pub enum ExitReason {
+    Exited(i32), // 调用sys_exit正常退出，带退出码
+    Fault { scause: usize, stval: usize }, // 触发异常被内核杀死
+    BadSyscall { syscall_id: usize, errno: isize }, // 系统调用出错，按内核策略被杀死
+    StackOverflow, // 用户栈溢出被内核杀死
+}
+
+impl ExitReason {
+    // 是否是被内核杀死的，而不是自己调用sys_exit退出的
+    pub fn killed(&self) -> bool {
+        !matches!(self, ExitReason::Exited(_))
+    }
+
+    // 退出码，被内核杀死的任务统一为-1
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExitReason::Exited(code) => *code,
+            _ => -1,
+        }
+    }
+}
+
+impl TaskControlBlock {
+    // 空的任务控制块，任务表中还没有使用的位置都是它
+    pub fn empty() -> Self {
+        Self {
+            task_cx: TaskContext::zero_init(),
+            task_status: TaskStatus::UnInit,
+            task_app_id: 0,
+            task_syscall_times: [0; MAX_SYSCALL_NUM],
+            task_first_running_time: None,
+            task_priority: DEFAULT_PRIORITY,
+            task_stride: BIG_STRIDE / DEFAULT_PRIORITY,
+            task_pass: 0,
+            task_time_slice: DEFAULT_TIME_SLICE_US,
+            task_user_time: 0,
+            task_kernel_time: 0,
+            task_last_stamp: 0,
+            task_voluntary_switches: 0,
+            task_preemptions: 0,
+            task_scheduled: 0,
+            task_ready_time: 0,
+            task_ready_stamp: 0,
+            task_exit_reason: None,
+        }
+    }
+
+    // 任务是否还占用着它的应用映像，占用时同一个应用不能再启动一份
+    pub fn is_alive(&self) -> bool {
+        !matches!(self.task_status, TaskStatus::UnInit | TaskStatus::Exited)
+    }
+
+    // 设置优先级，同时更新步长，步长至少为1，否则行程不再增长会饿死其他任务
+    pub fn set_priority(&mut self, prio: usize) {
+        self.task_priority = prio;
+        self.task_stride = (BIG_STRIDE / prio).max(1);
+    }
+
+    // 结算从上次计时到现在的用户态时间
+    pub fn account_user_time(&mut self, now: usize) {
+        self.task_user_time += now - self.task_last_stamp;
+        self.task_last_stamp = now;
+    }
+
+    // 结算从上次计时到现在的内核态时间
+    pub fn account_kernel_time(&mut self, now: usize) {
+        self.task_kernel_time += now - self.task_last_stamp;
+        self.task_last_stamp = now;
+    }
+
+    // 进入就绪态，开始计算等待时间
+    pub fn make_ready(&mut self, now: usize) {
+        self.task_status = TaskStatus::Ready;
+        self.task_ready_stamp = now;
+    }
+
+    // 被选中运行，结算等待时间
+    pub fn make_running(&mut self, now: usize) {
+        self.task_status = TaskStatus::Running;
+        self.task_ready_time += now - self.task_ready_stamp;
+        self.task_scheduled += 1;
+    }
+
+    // 被调度一次，行程前进一个步长，允许回绕，比较时用pass_lt
+    pub fn advance_pass(&mut self) {
+        self.task_pass = self.task_pass.wrapping_add(self.task_stride);
+    }
+}
+
+// 比较两个行程的先后。因为优先级至少为2，就绪任务之间行程差不超过BIG_STRIDE / 2，
+// 所以把差值当作有符号数比较，即使行程发生了回绕也能得到正确结果
+pub fn pass_lt(a: usize, b: usize) -> bool {
+    (a.wrapping_sub(b) as isize) < 0
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -20,4 +131,5 @@ pub enum TaskStatus {
     Ready, //挂起
     Running, // 正运行
     Exited, // 已结束
+    Sleeping, // 睡眠中，等待定时器到期唤醒，不参与调度
 }
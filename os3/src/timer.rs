@@ -6,9 +6,19 @@
 // 好在运行在 M 特权级的 SEE （这里是RustSBI）已经预留了相应的接口，在sbi.rs中的set_timer封装。
 use crate::config::CLOCK_FREQ; // 预先获取到的各平台不同的时钟频率，单位为赫兹，也就是一秒钟之内计数器的增量。
 use crate::sbi::set_timer;
+use crate::sync::UPSafeCell;
+use crate::task::wakeup_task;
+use alloc::collections::BinaryHeap;
+use core::cmp::Ordering;
+use lazy_static::*;
 use riscv::register::time;
 
 const TICKS_PER_SEC: usize = 100;
+
+// 编译时设置了环境变量TICKLESS时不再周期性地产生时钟中断，
+// 只在当前任务的时间片用完或者最早的睡眠任务到期时触发，例如 TICKLESS=1 make run
+const TICKLESS: bool = option_env!("TICKLESS").is_some();
+const MILLI_PER_SEC: usize = 1_000;
 const MICRO_PER_SEC: usize = 1_000_000;
 
 pub fn get_time() -> usize {
@@ -19,8 +29,99 @@ pub fn get_time_us() -> usize {
     time::read() / (CLOCK_FREQ / MICRO_PER_SEC)
 }
 
+pub fn get_time_ms() -> usize {
+    time::read() / (CLOCK_FREQ / MILLI_PER_SEC)
+}
+
 // 它首先读取当前 mtime 的值，然后计算出 10ms 之内计数器的增量，再将 mtimecmp 设置为二者的和。
 // 这样，10ms 之后一个 S 特权级时钟中断就会被触发。反复调用则会实现按时中断。
+// 如果有睡眠任务在这之前到期，则提前到它的到期时刻触发，保证睡眠任务按时被唤醒。
+// 无时钟节拍模式下用当前任务时间片的结束时刻代替10ms之后，两者都没有时不设置中断。
 pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+    let mut next = if TICKLESS {
+        SLICE_DEADLINE.exclusive_access().unwrap_or(usize::MAX)
+    } else {
+        get_time() + CLOCK_FREQ / TICKS_PER_SEC
+    };
+    if let Some(timer) = TIMERS.exclusive_access().peek() {
+        // 溢出时回绕成过去的时刻会让时钟中断立即反复触发，饱和到最大值
+        let expire = timer
+            .expire_ms
+            .checked_mul(CLOCK_FREQ / MILLI_PER_SEC)
+            .unwrap_or(usize::MAX);
+        next = next.min(expire);
+    }
+    set_timer(next);
+}
+
+lazy_static! {
+    // 当前任务时间片结束的时刻，单位为计数器的周期，None表示不限
+    static ref SLICE_DEADLINE: UPSafeCell<Option<usize>> = unsafe { UPSafeCell::new(None) };
+}
+
+// 切换到新任务时开始一个长为slice_us微秒的时间片，并重新设置时钟中断
+// 周期模式下时钟节拍也从现在重新开始计，保证时间片的结束时刻不会落在两个节拍之间而被多算一拍
+// slice_us为None时不限时间片，用于非抢占式调度和空闲等待
+pub fn set_time_slice(slice_us: Option<usize>) {
+    *SLICE_DEADLINE.exclusive_access() =
+        slice_us.map(|us| get_time() + us * (CLOCK_FREQ / MICRO_PER_SEC));
+    set_next_trigger();
+}
+
+// 当前任务的时间片是否已经用完
+pub fn time_slice_expired() -> bool {
+    SLICE_DEADLINE
+        .exclusive_access()
+        .map_or(false, |deadline| get_time() >= deadline)
+}
+
+// 睡眠任务的定时器，到期时唤醒对应的任务
+pub struct TimerCondVar {
+    pub expire_ms: usize, // 到期时刻
+    pub task_id: usize, // 要唤醒的任务
+}
+
+impl PartialEq for TimerCondVar {
+    fn eq(&self, other: &Self) -> bool {
+        self.expire_ms == other.expire_ms
+    }
+}
+impl Eq for TimerCondVar {}
+impl PartialOrd for TimerCondVar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// BinaryHeap是大根堆，这里反过来比较，让最早到期的定时器在堆顶
+impl Ord for TimerCondVar {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.expire_ms.cmp(&self.expire_ms)
+    }
+}
+
+lazy_static! {
+    // 按到期时刻排序的定时器队列
+    static ref TIMERS: UPSafeCell<BinaryHeap<TimerCondVar>> =
+        unsafe { UPSafeCell::new(BinaryHeap::<TimerCondVar>::new()) };
+}
+
+// 添加一个定时器，到期时唤醒task_id对应的任务
+pub fn add_timer(expire_ms: usize, task_id: usize) {
+    let mut timers = TIMERS.exclusive_access();
+    timers.push(TimerCondVar { expire_ms, task_id });
+}
+
+// 唤醒所有已经到期的睡眠任务
+pub fn check_timer() {
+    let current_ms = get_time_ms();
+    let mut timers = TIMERS.exclusive_access();
+    while let Some(timer) = timers.peek() {
+        if timer.expire_ms <= current_ms {
+            wakeup_task(timer.task_id);
+            timers.pop();
+        } else {
+            break;
+        }
+    }
 }
@@ -0,0 +1,95 @@
+// 内核事件跟踪：把陷入、系统调用、任务切换和时钟中断按时间记录在内存中的环形缓冲区里
+// 比起通过SBI逐行打印日志，记录一条事件只要几十条指令，几乎不影响调度的时序
+// panic时或者应用调用sys_trace_dump时以十六进制输出，用trace_decode.py在宿主机上解码成时间线
+
+use crate::config::TRACE_CAPACITY;
+use crate::sync::UPSafeCell;
+use crate::timer::get_time_us;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+// 事件类型，与trace_decode.py中的定义保持一致
+#[derive(Copy, Clone)]
+#[repr(u32)]
+pub enum TraceKind {
+    TrapEnter = 1,  // args: scause, sepc
+    TrapExit = 2,   // args: 无
+    Syscall = 3,    // args: syscall_id, a0, a1, a2
+    SyscallRet = 4, // args: syscall_id, 返回值
+    Switch = 5,     // args: 切换前的任务，切换后的任务
+    Tick = 6,       // args: 无
+}
+
+// 一条事件记录，按小端序原样输出，共48字节
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct TraceRecord {
+    time: u64,      // 记录时刻，单位微秒
+    kind: u32,      // 事件类型
+    task: u32,      // 事件发生时正在运行的任务
+    args: [u64; 4], // 事件参数
+}
+
+// 环形缓冲区，写满后覆盖最旧的记录
+struct TraceBuffer {
+    records: Vec<TraceRecord>,
+    head: usize, // 写满之后，下一条记录要覆盖的位置，也就是最旧的记录
+    task: u32,   // 当前运行的任务，由Switch事件更新
+}
+
+lazy_static! {
+    static ref TRACE: UPSafeCell<TraceBuffer> = unsafe {
+        UPSafeCell::new(TraceBuffer {
+            records: Vec::with_capacity(TRACE_CAPACITY),
+            head: 0,
+            task: 0,
+        })
+    };
+}
+
+// 记录一条事件，不足四个的参数补零
+pub fn trace(kind: TraceKind, args: &[usize]) {
+    let mut buf = TRACE.exclusive_access();
+    if let TraceKind::Switch = kind {
+        buf.task = args[1] as u32;
+    }
+    let mut record = TraceRecord {
+        time: get_time_us() as u64,
+        kind: kind as u32,
+        task: buf.task,
+        args: [0; 4],
+    };
+    record
+        .args
+        .iter_mut()
+        .zip(args)
+        .for_each(|(dst, &src)| *dst = src as u64);
+    if buf.records.len() < TRACE_CAPACITY {
+        buf.records.push(record);
+    } else {
+        let head = buf.head;
+        buf.records[head] = record;
+        buf.head = (head + 1) % TRACE_CAPACITY;
+    }
+}
+
+// 从旧到新输出所有记录，每条一行十六进制，前后加上trace_decode.py识别的标记，返回输出的记录数
+pub fn dump_trace() -> usize {
+    let buf = TRACE.exclusive_access();
+    let (newer, older) = buf.records.split_at(buf.head);
+    println!("[trace] begin {}", buf.records.len());
+    for record in older.iter().chain(newer) {
+        let bytes = unsafe {
+            core::slice::from_raw_parts(
+                record as *const TraceRecord as *const u8,
+                core::mem::size_of::<TraceRecord>(),
+            )
+        };
+        for byte in bytes {
+            print!("{:02x}", byte);
+        }
+        println!();
+    }
+    println!("[trace] end");
+    buf.records.len()
+}
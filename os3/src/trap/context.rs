@@ -2,6 +2,13 @@
 
 use riscv::register::sstatus::{self, Sstatus, SPP};
 
+// 32个通用寄存器的ABI名称，打印崩溃报告用
+const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
 // 定义TrapContext，为了给trap_handler用，通过Trap.S手动完成调用约定，构成了汇编与Rust之间的桥梁
 #[repr(C)] // C布局，这样就和我们在Trap.S中入栈的顺序完美契合了，当a0中放入sp地址时，就可以将手动入栈的那些作为trap_handler参数
 pub struct TrapContext {
@@ -29,4 +36,19 @@ impl TrapContext {
         cx.set_sp(sp); // sp 寄存器为我们设定的用户栈指针
         cx // 返回构造好的上下文
     }
+
+    // 打印陷入时保存的全部寄存器，每行四个
+    pub fn dump(&self) {
+        for (i, chunk) in self.x.chunks(4).enumerate() {
+            for (j, reg) in chunk.iter().enumerate() {
+                print!("{:>4}(x{:<2}) = {:#018x}  ", REG_NAMES[i * 4 + j], i * 4 + j, reg);
+            }
+            println!();
+        }
+        println!(
+            "sepc = {:#018x}  sstatus = {:#018x}",
+            self.sepc,
+            self.sstatus.bits()
+        );
+    }
 }
@@ -1,9 +1,16 @@
 // 使用上下文模块
 mod context;
 
+use crate::console::handle_interrupt;
+use crate::loader::get_app_name;
 use crate::syscall::syscall;
-use crate::task::{exit_current_and_run_next, suspend_current_and_run_next};
-use crate::timer::set_next_trigger;
+use crate::task::{
+    account_trap_enter, account_trap_exit, check_current_stack, current_app_id,
+    exit_current_and_run_next,
+    is_preemptive, preempt_current_and_run_next, ExitReason,
+};
+use crate::timer::{check_timer, set_next_trigger, time_slice_expired};
+use crate::trace::{trace, TraceKind};
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
@@ -37,9 +44,12 @@ pub fn enable_timer_interrupt() {
 // trap.S处理完以后会跳转至这里
 // 
 pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
+    account_trap_enter(); // 结算用户态时间
+    check_current_stack(); // 检查栈有没有溢出
 
     let scause = scause::read(); // 获取陷入原因，这俩都不是在cx上下文中的
     let stval = stval::read(); // 获取额外数据
+    trace(TraceKind::TrapEnter, &[scause.bits(), cx.sepc]);
 
     // 根据陷入原因进行分发处理
     match scause.cause() {
@@ -57,20 +67,36 @@ pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
             cx.x[10] = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]) as usize;
 
         }
-        // 访问无权限访问的地址
-        Trap::Exception(Exception::StoreFault) | Trap::Exception(Exception::StorePageFault) => {
-            error!("[kernel] PageFault in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.", stval, cx.sepc);
-            exit_current_and_run_next();
-        }
-        // 使用非法指令
-        Trap::Exception(Exception::IllegalInstruction) => {
-            error!("[kernel] IllegalInstruction in application, core dumped.");
-            exit_current_and_run_next();
+        // 其余所有来自应用的异常（访存错误、非法指令、断点等），只杀死出错的应用
+        Trap::Exception(e) => {
+            println!(
+                "[kernel] {:?} in application {}, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                e,
+                get_app_name(current_app_id()),
+                stval,
+                cx.sepc
+            );
+            println!("scause = {:#x}  stval = {:#x}", scause.bits(), stval);
+            cx.dump();
+            exit_current_and_run_next(ExitReason::Fault {
+                scause: scause.bits(),
+                stval,
+            });
         }
         // 时钟中断
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            trace(TraceKind::Tick, &[]);
+            // 先唤醒到期的睡眠任务，再设置下一次中断
+            check_timer();
             set_next_trigger();
-            suspend_current_and_run_next();
+            // 当前任务的时间片用完才切换，非抢占式的调度策略下不切换
+            if is_preemptive() && time_slice_expired() {
+                preempt_current_and_run_next();
+            }
+        }
+        // 外部中断，目前只有串口的输入
+        Trap::Interrupt(Interrupt::SupervisorExternal) => {
+            handle_interrupt();
         }
         // 未知陷入
         _ => {
@@ -81,6 +107,8 @@ pub fn trap_handler(cx: &mut TrapContext) -> &mut TrapContext {
             );
         }
     }
+    trace(TraceKind::TrapExit, &[]);
+    account_trap_exit(); // 结算内核态时间
     cx
 }
 
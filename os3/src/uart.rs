@@ -0,0 +1,190 @@
+// QEMU virt板上的NS16550A串口驱动，启用uart特性时代替SBI控制台
+// 输出先放进发送环形缓冲区，每次发送寄存器空时连续写满16字节的FIFO，不再每个字符陷入一次M态
+// 缓冲区里剩下的数据在发送寄存器空中断到来时继续发送，缓冲区满了就原地轮询等待
+// 输入由PLIC转发的外部中断驱动，中断处理时把到达的字符收进环形缓冲区，sys_read从缓冲区取
+
+use crate::sync::UPSafeCell;
+use lazy_static::*;
+use riscv::register::sie;
+
+// QEMU virt板上串口和PLIC的地址，以及串口的中断号
+const UART_BASE: usize = 0x1000_0000;
+const UART_IRQ: u32 = 10;
+const PLIC_BASE: usize = 0x0c00_0000;
+
+// 寄存器偏移
+const RBR: usize = 0; // 接收缓冲，只读
+const THR: usize = 0; // 发送保持，只写
+const IER: usize = 1; // 中断使能
+const FCR: usize = 2; // FIFO控制，只写
+const LCR: usize = 3; // 线路控制
+const MCR: usize = 4; // 调制解调器控制
+const LSR: usize = 5; // 线路状态
+
+const IER_RX_AVAILABLE: u8 = 1 << 0;
+const IER_THR_EMPTY: u8 = 1 << 1;
+const FCR_ENABLE_AND_CLEAR: u8 = 0x07;
+const LCR_8N1: u8 = 0x03;
+const MCR_DTR_RTS_OUT2: u8 = 0x0b; // OUT2要置位，中断才会送到PLIC
+const LSR_DATA_READY: u8 = 1 << 0;
+const LSR_THR_EMPTY: u8 = 1 << 5;
+
+// 发送FIFO的深度
+const TX_FIFO_SIZE: usize = 16;
+
+// PLIC中hart 0的S态上下文
+const PLIC_CONTEXT: usize = 1;
+
+fn read_reg(reg: usize) -> u8 {
+    unsafe { ((UART_BASE + reg) as *const u8).read_volatile() }
+}
+
+fn write_reg(reg: usize, value: u8) {
+    unsafe { ((UART_BASE + reg) as *mut u8).write_volatile(value) }
+}
+
+fn plic_reg(offset: usize) -> *mut u32 {
+    (PLIC_BASE + offset) as *mut u32
+}
+
+// 接收环形缓冲区的大小，满了以后丢弃新到的字符
+const RX_BUFFER_SIZE: usize = 256;
+
+struct RxBuffer {
+    data: [u8; RX_BUFFER_SIZE],
+    head: usize, // 下一个要读出的位置
+    len: usize,
+}
+
+// 发送环形缓冲区的大小
+const TX_BUFFER_SIZE: usize = 1024;
+
+struct TxBuffer {
+    data: [u8; TX_BUFFER_SIZE],
+    head: usize, // 下一个要发送的位置
+    len: usize,
+}
+
+lazy_static! {
+    static ref RX_BUFFER: UPSafeCell<RxBuffer> = unsafe {
+        UPSafeCell::new(RxBuffer {
+            data: [0; RX_BUFFER_SIZE],
+            head: 0,
+            len: 0,
+        })
+    };
+    static ref TX_BUFFER: UPSafeCell<TxBuffer> = unsafe {
+        UPSafeCell::new(TxBuffer {
+            data: [0; TX_BUFFER_SIZE],
+            head: 0,
+            len: 0,
+        })
+    };
+}
+
+// 初始化串口并通过PLIC打开它的接收中断
+pub fn init() {
+    write_reg(IER, 0);
+    write_reg(LCR, LCR_8N1);
+    write_reg(FCR, FCR_ENABLE_AND_CLEAR);
+    write_reg(MCR, MCR_DTR_RTS_OUT2);
+    write_reg(IER, IER_RX_AVAILABLE);
+    unsafe {
+        // 中断源优先级设为1，上下文的阈值设为0，这样串口中断能够通过
+        plic_reg(4 * UART_IRQ as usize).write_volatile(1);
+        let enable = plic_reg(0x2000 + 0x80 * PLIC_CONTEXT);
+        enable.write_volatile(enable.read_volatile() | 1 << UART_IRQ);
+        plic_reg(0x20_0000 + 0x1000 * PLIC_CONTEXT).write_volatile(0);
+        sie::set_sext();
+    }
+}
+
+// 发送寄存器空时把缓冲区开头的一段写进FIFO，不等待
+fn drain_tx(buf: &mut TxBuffer) {
+    if buf.len == 0 || read_reg(LSR) & LSR_THR_EMPTY == 0 {
+        return;
+    }
+    for _ in 0..buf.len.min(TX_FIFO_SIZE) {
+        write_reg(THR, buf.data[buf.head]);
+        buf.head = (buf.head + 1) % TX_BUFFER_SIZE;
+        buf.len -= 1;
+    }
+}
+
+// 缓冲区里还有数据时打开发送寄存器空中断，由中断继续发送，发完后关掉
+fn update_tx_interrupt(buf: &TxBuffer) {
+    if buf.len == 0 {
+        write_reg(IER, IER_RX_AVAILABLE);
+    } else {
+        write_reg(IER, IER_RX_AVAILABLE | IER_THR_EMPTY);
+    }
+}
+
+// 输出一段字节，先放进发送缓冲区，缓冲区满时轮询发送腾出空间
+pub fn write_bytes(bytes: &[u8]) {
+    let mut buf = TX_BUFFER.exclusive_access();
+    for &b in bytes {
+        while buf.len == TX_BUFFER_SIZE {
+            drain_tx(&mut buf);
+        }
+        let tail = (buf.head + buf.len) % TX_BUFFER_SIZE;
+        buf.data[tail] = b;
+        buf.len += 1;
+    }
+    drain_tx(&mut buf);
+    update_tx_interrupt(&buf);
+}
+
+// 轮询直到缓冲区里的数据全部写进FIFO，关机前调用，避免丢掉最后的输出
+pub fn flush() {
+    let mut buf = TX_BUFFER.exclusive_access();
+    while buf.len > 0 {
+        drain_tx(&mut buf);
+    }
+    update_tx_interrupt(&buf);
+    // 等FIFO本身也发送完
+    while read_reg(LSR) & LSR_THR_EMPTY == 0 {}
+}
+
+// 把串口中已经到达的字符都收进缓冲区
+fn drain_rx(buf: &mut RxBuffer) {
+    while read_reg(LSR) & LSR_DATA_READY != 0 {
+        let c = read_reg(RBR);
+        if buf.len < RX_BUFFER_SIZE {
+            let tail = (buf.head + buf.len) % RX_BUFFER_SIZE;
+            buf.data[tail] = c;
+            buf.len += 1;
+        }
+    }
+}
+
+// 读一个字符，没有输入时返回None
+// 在内核中不会响应中断，所以除了缓冲区还要直接看一下串口
+pub fn getchar() -> Option<u8> {
+    let mut buf = RX_BUFFER.exclusive_access();
+    drain_rx(&mut buf);
+    if buf.len == 0 {
+        return None;
+    }
+    let c = buf.data[buf.head];
+    buf.head = (buf.head + 1) % RX_BUFFER_SIZE;
+    buf.len -= 1;
+    Some(c)
+}
+
+// 处理PLIC转发的外部中断，没有等待中的中断时什么也不做
+pub fn handle_interrupt() {
+    let claim = plic_reg(0x20_0004 + 0x1000 * PLIC_CONTEXT);
+    let irq = unsafe { claim.read_volatile() };
+    if irq == 0 {
+        return;
+    }
+    if irq == UART_IRQ {
+        drain_rx(&mut RX_BUFFER.exclusive_access());
+        let mut buf = TX_BUFFER.exclusive_access();
+        drain_tx(&mut buf);
+        update_tx_interrupt(&buf);
+    }
+    // 写回中断号表示处理完成
+    unsafe { claim.write_volatile(irq) };
+}
@@ -0,0 +1,78 @@
+# 解码内核跟踪缓冲区的输出，打印成时间线
+# 用法：make run | tee qemu.log 之后 python3 trace_decode.py qemu.log，不给文件名时从标准输入读取
+# 记录格式见src/trace.rs：time: u64, kind: u32, task: u32, args: [u64; 4]，小端序共48字节
+import os
+import re
+import struct
+import sys
+
+RECORD = struct.Struct("<QII4Q")
+
+SCAUSE = {
+    0: "InstructionMisaligned",
+    1: "InstructionFault",
+    2: "IllegalInstruction",
+    3: "Breakpoint",
+    4: "LoadMisaligned",
+    5: "LoadFault",
+    6: "StoreMisaligned",
+    7: "StoreFault",
+    8: "UserEnvCall",
+    9: "SupervisorEnvCall",
+    12: "InstructionPageFault",
+    13: "LoadPageFault",
+    15: "StorePageFault",
+    (1 << 63) | 1: "SupervisorSoft",
+    (1 << 63) | 5: "SupervisorTimer",
+    (1 << 63) | 9: "SupervisorExternal",
+}
+
+# 系统调用号直接从内核源码src/syscall/mod.rs中解析，新增系统调用时不用同步修改这里
+SYSCALL_SOURCE = os.path.join(os.path.dirname(os.path.abspath(__file__)), "src", "syscall", "mod.rs")
+with open(SYSCALL_SOURCE) as f:
+    SYSCALL = {
+        int(num): name.lower()
+        for name, num in re.findall(r"const SYSCALL_(\w+): usize = (\d+);", f.read())
+    }
+
+
+def signed(value):
+    return value - (1 << 64) if value >= 1 << 63 else value
+
+
+def describe(kind, args):
+    if kind == 1:
+        return "trap enter %s sepc=%#x" % (SCAUSE.get(args[0], hex(args[0])), args[1])
+    if kind == 2:
+        return "trap exit"
+    if kind == 3:
+        name = SYSCALL.get(args[0], str(args[0]))
+        return "syscall %s(%#x, %#x, %#x)" % (name, args[1], args[2], args[3])
+    if kind == 4:
+        return "syscall %s -> %d" % (SYSCALL.get(args[0], str(args[0])), signed(args[1]))
+    if kind == 5:
+        return "switch %d -> %d" % (args[0], args[1])
+    if kind == 6:
+        return "tick"
+    return "unknown kind %d %s" % (kind, args)
+
+
+def decode(lines):
+    dumping = False
+    start = None
+    for line in lines:
+        line = line.strip()
+        if line.startswith("[trace] begin"):
+            dumping, start = True, None
+            print("---- trace dump, %s records ----" % line.split()[-1])
+        elif line.startswith("[trace] end"):
+            dumping = False
+        elif dumping:
+            time, kind, task, *args = RECORD.unpack(bytes.fromhex(line))
+            start = time if start is None else start
+            print("%12.3f ms  task %-3d %s" % ((time - start) / 1000, task, describe(kind, args)))
+
+
+if __name__ == "__main__":
+    with open(sys.argv[1]) if len(sys.argv) > 1 else sys.stdin as f:
+        decode(f)
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, sleep_blocking, task_info_of, task_list, TaskInfoEx, TaskListEntry, TaskStatus};
+
+// 每隔一段时间打印一次内核任务表，其他任务都结束后退出
+
+const MAX_TASKS: usize = 32;
+const INTERVAL_MS: usize = 500;
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut entries = [TaskListEntry::new(); MAX_TASKS];
+    let mut info = TaskInfoEx::new();
+    loop {
+        let total = task_list(&mut entries);
+        assert!(total >= 0);
+        let shown = (total as usize).min(MAX_TASKS);
+        println!("---- top @ {} ms, {} tasks ----", get_time(), total);
+        println!(
+            "{:>3} {:<24} {:<9} {:>8} {:>6} {:>6} {:>7} {:>10}",
+            "ID", "NAME", "STATUS", "TIME(ms)", "SCHED", "VOL", "PREEMPT", "READY(ms)"
+        );
+        let mut others_alive = false;
+        for entry in entries[..shown].iter() {
+            if entry.status() == TaskStatus::UnInit || task_info_of(entry.id, &mut info) != 0 {
+                continue;
+            }
+            if matches!(entry.status(), TaskStatus::Ready | TaskStatus::Sleeping) {
+                others_alive = true;
+            }
+            println!(
+                "{:>3} {:<24} {:<9} {:>8} {:>6} {:>6} {:>7} {:>10}",
+                entry.id,
+                entry.name(),
+                match entry.status() {
+                    TaskStatus::Ready => "Ready",
+                    TaskStatus::Running => "Running",
+                    TaskStatus::Sleeping => "Sleeping",
+                    TaskStatus::Exited => "Exited",
+                    TaskStatus::UnInit => "UnInit",
+                },
+                info.info.time,
+                info.stats.scheduled,
+                info.stats.voluntary_switches,
+                info.stats.preemptions,
+                info.stats.ready_time / 1000
+            );
+        }
+        if !others_alive {
+            break;
+        }
+        sleep_blocking(INTERVAL_MS);
+    }
+    0
+}
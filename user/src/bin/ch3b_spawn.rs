@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{spawn, task_list, yield_, TaskListEntry, TaskStatus};
+
+/// 正确输出：（无报错信息）
+/// Test spawn OK!
+
+const ENOENT: isize = -2;
+const EBUSY: isize = -16;
+
+const MAX_TASKS: usize = 32;
+const TARGET: &str = "ch2b_hello_world\0";
+
+// 让出CPU直到task_id对应的任务结束
+fn wait_exit(task_id: usize) {
+    let mut entries = [TaskListEntry::new(); MAX_TASKS];
+    loop {
+        let total = task_list(&mut entries);
+        assert!(task_id < MAX_TASKS && total > task_id as isize);
+        if entries[task_id].status() == TaskStatus::Exited {
+            return;
+        }
+        yield_();
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    // 没有这个应用
+    assert_eq!(spawn("ch3b_no_such_app\0"), ENOENT);
+    // 自己正在运行，不能再启动一个
+    assert_eq!(spawn("ch3b_spawn\0"), EBUSY);
+
+    // 开机时启动的那个任务可能还没有结束，等它结束后才能启动
+    let first = loop {
+        match spawn(TARGET) {
+            EBUSY => {
+                yield_();
+            }
+            id => break id,
+        }
+    };
+    assert!(first >= 0);
+    wait_exit(first as usize);
+
+    // 结束之后可以再次启动
+    let second = spawn(TARGET);
+    assert!(second >= 0);
+    wait_exit(second as usize);
+    println!("Test spawn OK!");
+    0
+}
@@ -104,6 +104,7 @@ pub enum TaskStatus {
     Ready,
     Running,
     Exited,
+    Sleeping,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -131,6 +132,86 @@ impl TaskInfo {
     }
 }
 
+/// Scheduling statistics of the current task, times in microseconds
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TaskStats {
+    pub voluntary_switches: usize,
+    pub preemptions: usize,
+    pub scheduled: usize,
+    pub ready_time: usize,
+    /// Only meaningful once the task has exited; -1 if it was killed by the kernel
+    pub exit_code: isize,
+}
+
+/// Version 1 of `task_info`: the original `TaskInfo` followed by `TaskStats`
+#[repr(C)]
+#[derive(Debug)]
+pub struct TaskInfoEx {
+    pub info: TaskInfo,
+    pub stats: TaskStats,
+}
+
+impl TaskInfoEx {
+    pub fn new() -> Self {
+        TaskInfoEx {
+            info: TaskInfo::new(),
+            stats: TaskStats::default(),
+        }
+    }
+}
+
+/// Values of `TaskListEntry::status`, matching the kernel's `TASK_STATUS_*`
+pub const TASK_STATUS_UNINIT: usize = 0;
+pub const TASK_STATUS_READY: usize = 1;
+pub const TASK_STATUS_RUNNING: usize = 2;
+pub const TASK_STATUS_EXITED: usize = 3;
+pub const TASK_STATUS_SLEEPING: usize = 4;
+
+/// One entry of the kernel task table, filled in by `task_list`
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct TaskListEntry {
+    pub id: usize,
+    /// One of the `TASK_STATUS_*` values; use `status()` to decode it
+    pub status: usize,
+    /// App name, NUL padded; empty for unused slots
+    pub name: [u8; 32],
+}
+
+impl TaskListEntry {
+    pub fn new() -> Self {
+        TaskListEntry {
+            id: 0,
+            status: TASK_STATUS_UNINIT,
+            name: [0; 32],
+        }
+    }
+
+    pub fn status(&self) -> TaskStatus {
+        match self.status {
+            TASK_STATUS_READY => TaskStatus::Ready,
+            TASK_STATUS_RUNNING => TaskStatus::Running,
+            TASK_STATUS_EXITED => TaskStatus::Exited,
+            TASK_STATUS_SLEEPING => TaskStatus::Sleeping,
+            _ => TaskStatus::UnInit,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|&c| c == 0).unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..len]).unwrap_or("?")
+    }
+}
+
+/// CPU time of the current task, in microseconds
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TaskTimes {
+    pub utime: usize,
+    pub stime: usize,
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct Stat {
@@ -307,6 +388,37 @@ pub fn task_info(info: &TaskInfo) -> isize {
     sys_task_info(info)
 }
 
+pub fn task_info_ex(info: &mut TaskInfoEx) -> isize {
+    sys_task_info_ex(info)
+}
+
+pub fn task_info_of(task_id: usize, info: &mut TaskInfoEx) -> isize {
+    sys_task_info_of(task_id, info)
+}
+
+/// Fills `entries` with the kernel task table and returns the total number of tasks,
+/// which may exceed `entries.len()`
+pub fn task_list(entries: &mut [TaskListEntry]) -> isize {
+    sys_task_list(entries)
+}
+
+pub fn times(times: &mut TaskTimes) -> isize {
+    sys_times(times)
+}
+
+/// Sets how many milliseconds the current task may run before being preempted (1..=1000)
+pub fn set_time_slice(ms: usize) -> isize {
+    sys_set_time_slice(ms)
+}
+
+pub fn trace_dump() -> isize {
+    sys_trace_dump()
+}
+
+pub fn set_log_level(level: isize) -> isize {
+    sys_set_log_level(level)
+}
+
 pub fn thread_create(entry: usize, arg: usize) -> isize {
     sys_thread_create(entry, arg)
 }
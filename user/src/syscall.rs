@@ -1,4 +1,4 @@
-use crate::TaskInfo;
+use crate::{TaskInfo, TaskInfoEx, TaskListEntry, TaskTimes};
 
 use super::{Stat, TimeVal};
 
@@ -19,6 +19,7 @@ pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
 pub const SYSCALL_WAITPID: usize = 260;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
+pub const SYSCALL_TIMES: usize = 153;
 pub const SYSCALL_MUNMAP: usize = 215;
 pub const SYSCALL_MMAP: usize = 222;
 pub const SYSCALL_SPAWN: usize = 400;
@@ -27,6 +28,11 @@ pub const SYSCALL_MAIL_WRITE: usize = 402;
 pub const SYSCALL_DUP: usize = 24;
 pub const SYSCALL_PIPE: usize = 59;
 pub const SYSCALL_TASK_INFO: usize = 410;
+pub const SYSCALL_TRACE_DUMP: usize = 411;
+pub const SYSCALL_SET_LOG_LEVEL: usize = 412;
+pub const SYSCALL_TASK_INFO_OF: usize = 413;
+pub const SYSCALL_TASK_LIST: usize = 414;
+pub const SYSCALL_SET_TIME_SLICE: usize = 415;
 pub const SYSCALL_THREAD_CREATE: usize = 460;
 pub const SYSCALL_WAITTID: usize = 462;
 pub const SYSCALL_MUTEX_CREATE: usize = 463;
@@ -181,6 +187,10 @@ pub fn sys_set_priority(prio: isize) -> isize {
     syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
 }
 
+pub fn sys_times(times: &mut TaskTimes) -> isize {
+    syscall(SYSCALL_TIMES, [times as *mut _ as usize, 0, 0])
+}
+
 pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
     syscall(SYSCALL_MMAP, [start, len, prot])
 }
@@ -205,6 +215,33 @@ pub fn sys_task_info(info: &TaskInfo) -> isize {
     syscall(SYSCALL_TASK_INFO, [info as *const _ as usize, 0, 0])
 }
 
+pub fn sys_task_info_ex(info: &mut TaskInfoEx) -> isize {
+    syscall(SYSCALL_TASK_INFO, [info as *mut _ as usize, 1, 0])
+}
+
+pub fn sys_task_info_of(task_id: usize, info: &mut TaskInfoEx) -> isize {
+    syscall(SYSCALL_TASK_INFO_OF, [task_id, info as *mut _ as usize, 0])
+}
+
+pub fn sys_set_time_slice(ms: usize) -> isize {
+    syscall(SYSCALL_SET_TIME_SLICE, [ms, 0, 0])
+}
+
+pub fn sys_task_list(entries: &mut [TaskListEntry]) -> isize {
+    syscall(
+        SYSCALL_TASK_LIST,
+        [entries.as_mut_ptr() as usize, entries.len(), 0],
+    )
+}
+
+pub fn sys_trace_dump() -> isize {
+    syscall(SYSCALL_TRACE_DUMP, [0, 0, 0])
+}
+
+pub fn sys_set_log_level(level: isize) -> isize {
+    syscall(SYSCALL_SET_LOG_LEVEL, [level as usize, 0, 0])
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }